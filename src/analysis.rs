@@ -0,0 +1,39 @@
+use primitive_types::{U256};
+
+pub mod boundary;
+pub mod monotonic;
+pub mod invariant;
+pub mod metamorphic;
+pub mod rounding;
+pub mod plateau;
+pub mod anomaly;
+pub mod derivative;
+pub mod distribution;
+pub mod stats;
+pub mod profit;
+pub mod iterate;
+pub mod param_search;
+
+/// Narrows `[lo, hi]` down to adjacent integers `(a, a + 1)` with `pred(a) == false` and
+/// `pred(a + 1) == true`, given `pred(lo) == false` and `pred(hi) == true`.
+/// If `pred` changes more than once in the range, one of the changes is found
+pub fn bisect(mut lo: U256, mut hi: U256, mut pred: impl FnMut(U256) -> bool) -> (U256, U256) {
+    while hi - lo > U256::one() {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) { hi = mid } else { lo = mid }
+    }
+    (lo, hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bisect() -> Result<(), Box<dyn std::error::Error>> {
+        let threshold = U256::from(123_456_789u64);
+        let (a, b) = bisect(U256::zero(), U256::MAX, |x| x >= threshold);
+        assert_eq!((a, b), (threshold - 1, threshold));
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use primitive_types::{U256};
+
+use crate::sampling::Sample;
+
+/*
+ * Flags samples that stand out from their neighbours. Every change between neighbouring samples
+ * is measured against the typical change over the whole view, the median of the non-zero changes,
+ * so the threshold reads as "this many times bigger than usual". Reverting samples are skipped.
+ */
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnomalyKind {
+    /// A sample far from the line through its two neighbours, on neither side of both
+    Outlier,
+    /// A change between two neighbouring samples much bigger than usual
+    Jump,
+    /// The output drops to zero next to non-zero outputs
+    Collapse,
+}
+
+impl AnomalyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnomalyKind::Outlier => "outlier",
+            AnomalyKind::Jump => "jump",
+            AnomalyKind::Collapse => "collapse to 0",
+        }
+    }
+}
+
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub x: f64,
+    pub x_raw: U256,
+    pub y: f64,
+    /// Size of the anomaly in multiples of the typical change between samples
+    pub severity: f64,
+}
+
+/// Anomalies among the samples that produced a value, most severe first.
+/// A jump is reported at the sample after it
+pub fn detect_anomalies(samples: &[Sample], threshold: f64) -> Vec<Anomaly> {
+    let valid: Vec<(&Sample, f64)> = samples.iter().filter_map(|s| s.y.map(|y| (s, y))).collect();
+    let ys: Vec<f64> = valid.iter().map(|&(_, y)| y).collect();
+    let mut anomalies: Vec<Anomaly> = detect(&ys, threshold).into_iter()
+        .map(|(kind, i, severity)| {
+            let (s, y) = valid[i];
+            Anomaly { kind, x: s.x, x_raw: s.x_raw, y, severity }
+        })
+        .collect();
+    anomalies.sort_by(|a, b| b.severity.total_cmp(&a.severity));
+    anomalies
+}
+
+/// `(kind, index, severity)` of each anomaly in the series
+fn detect(ys: &[f64], threshold: f64) -> Vec<(AnomalyKind, usize, f64)> {
+    let mut changes: Vec<f64> = ys.windows(2).map(|w| (w[1] - w[0]).abs()).filter(|&d| d > 0.0).collect();
+    if changes.is_empty() {
+        return Vec::new();
+    }
+    changes.sort_by(f64::total_cmp);
+    let scale = changes[changes.len() / 2];
+
+    let mut anomalies = Vec::new();
+    let mut flagged = vec![false; ys.len()];
+
+    // Collapses first, since a collapse also looks like an outlier or a pair of jumps
+    for i in 0..ys.len() {
+        let neighbours = [i.checked_sub(1).map(|j| ys[j]), ys.get(i + 1).copied()];
+        let largest = neighbours.iter().flatten().fold(0.0_f64, |m, &y| m.max(y.abs()));
+        if ys[i] == 0.0 && largest > 0.0 && largest / scale > threshold {
+            anomalies.push((AnomalyKind::Collapse, i, largest / scale));
+            flagged[i] = true;
+        }
+    }
+    for i in 1..ys.len().saturating_sub(1) {
+        let (prev, y, next) = (ys[i - 1], ys[i], ys[i + 1]);
+        let residual = (y - (prev + next) / 2.0).abs();
+        // A sample in the middle of a step is at most half the step away from the trend
+        if !flagged[i] && residual > (next - prev).abs() && residual / scale > threshold {
+            anomalies.push((AnomalyKind::Outlier, i, residual / scale));
+            flagged[i] = true;
+        }
+    }
+    for i in 1..ys.len() {
+        let change = (ys[i] - ys[i - 1]).abs();
+        if !flagged[i - 1] && !flagged[i] && change / scale > threshold {
+            anomalies.push((AnomalyKind::Jump, i, change / scale));
+        }
+    }
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() -> Result<(), Box<dyn std::error::Error>> {
+        let mut ys: Vec<f64> = (0..40).map(|i| 100.0 + i as f64).collect();
+        ys[10] = 500.0;
+        ys[20] = 0.0;
+        for y in &mut ys[30..] {
+            *y += 1000.0;
+        }
+
+        let mut found = detect(&ys, 10.0);
+        found.sort_by_key(|&(_, i, _)| i);
+        let kinds: Vec<(AnomalyKind, usize)> = found.iter().map(|&(kind, i, _)| (kind, i)).collect();
+        assert_eq!(kinds, [(AnomalyKind::Outlier, 10), (AnomalyKind::Collapse, 20), (AnomalyKind::Jump, 30)]);
+
+        // A smooth series has nothing to report
+        let smooth: Vec<f64> = (0..40).map(|i| (i * i) as f64).collect();
+        assert!(detect(&smooth, 10.0).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use primitive_types::{U256};
+
+use crate::analysis::bisect;
+use crate::maths::*;
+use crate::sampling::{eval, with_silent_panics, Sample};
+
+/// An exact transition between inputs where the function succeeds and inputs where it reverts
+pub struct RevertBoundary {
+    /// Last input before the transition
+    pub before: U256,
+    /// First input after the transition, i.e. `before + 1`. This is the first failing input when the
+    /// function starts reverting and the first succeeding one when it stops
+    pub after: U256,
+    /// Whether the function reverts from `after` onwards (rather than up to `before`)
+    pub starts_reverting: bool,
+}
+
+/// Bisects between every pair of neighbouring samples where one reverts and the other doesn't,
+/// returning the exact boundaries in increasing order of input.
+/// A pair of samples hiding several transitions yields just one of them
+pub fn locate_revert_boundaries(fun: &FixedPointFunction, samples: &[Sample]) -> Vec<RevertBoundary> {
+    with_silent_panics(|| {
+        samples.windows(2)
+            .filter(|w| w[0].x_raw < w[1].x_raw && w[0].y.is_none() != w[1].y.is_none())
+            .map(|w| {
+                let starts_reverting = w[1].y.is_none();
+                let (before, after) = bisect(w[0].x_raw, w[1].x_raw, |x| eval(fun, x).is_none() == starts_reverting);
+                RevertBoundary { before, after, starts_reverting }
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+    use crate::sampling::{sample_curve_u256_safe, SampleOptions};
+
+    #[test]
+    fn test_locate_revert_boundaries() -> Result<(), Box<dyn std::error::Error>> {
+        let samples = sample_curve_u256_safe(&x_mul_inverse, &SampleOptions::default(), 5, 4e17, 6e17);
+        let boundaries = locate_revert_boundaries(&x_mul_inverse, &samples);
+        assert_eq!(boundaries.len(), 1);
+        assert!(boundaries[0].starts_reverting);
+        assert_eq!(boundaries[0].after, u256d("500000000000000000000000000000000001"));
+        Ok(())
+    }
+}
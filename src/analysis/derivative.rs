@@ -0,0 +1,84 @@
+use num_bigint::BigInt;
+use primitive_types::{U256};
+
+use crate::maths::*;
+use crate::rational::{u256_to_bigint, Rational};
+use crate::sampling::{eval, with_silent_panics, Sample};
+
+/// The step `h` of the forward difference `(f(x+h) - f(x)) / h`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Step {
+    /// One raw unit of input
+    Wei,
+    /// The distance to the next sample, so no extra evaluations are needed
+    Spacing,
+    /// A fixed number of raw units
+    Raw(U256),
+}
+
+/// The forward difference at each sample, exactly, in raw output units per raw input unit.
+/// Samples where `f(x)` or `f(x+h)` reverts, or `x+h` overflows, are skipped
+pub fn derivative(fun: &FixedPointFunction, samples: &[Sample], step: Step) -> Vec<(f64, Rational)> {
+    let slope = |x0: U256, y0: U256, x1: U256, y1: U256| {
+        Rational::new(u256_to_bigint(y1) - u256_to_bigint(y0), u256_to_bigint(x1) - u256_to_bigint(x0))
+    };
+    match step {
+        Step::Spacing => samples.windows(2)
+            .filter_map(|w| {
+                let (y0, y1) = (w[0].y_raw?, w[1].y_raw?);
+                (w[0].x_raw < w[1].x_raw).then(|| (w[0].x, slope(w[0].x_raw, y0, w[1].x_raw, y1)))
+            })
+            .collect(),
+        Step::Wei | Step::Raw(_) => {
+            let h = if let Step::Raw(h) = step { h } else { U256::one() };
+            if h.is_zero() {
+                return Vec::new();
+            }
+            with_silent_panics(|| {
+                samples.iter()
+                    .filter_map(|s| {
+                        let y0 = s.y_raw?;
+                        let x1 = s.x_raw.checked_add(h)?;
+                        let y1 = eval(fun, x1)?;
+                        Some((s.x, slope(s.x_raw, y0, x1, y1)))
+                    })
+                    .collect()
+            })
+        }
+    }
+}
+
+/// A slope in raw units converted to display units of the function's output per display unit of input
+pub fn to_display_units(slope: &Rational, fun: &FixedPointFunction) -> f64 {
+    let (xb, yb) = (&fun.x_bounds, &fun.y_bounds);
+    let factor = Rational::new(BigInt::from(xb.radix).pow(xb.places), BigInt::from(yb.radix).pow(yb.places));
+    (slope * &factor).to_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::curve::curve_get_D;
+    use crate::sampling::{sample_curve_u256_safe, SampleOptions};
+
+    #[test]
+    fn test_derivative() -> Result<(), Box<dyn std::error::Error>> {
+        let samples = sample_curve_u256_safe(&curve_get_D, &SampleOptions::default(), 10, 0.5, 2.0);
+        let f = curve_get_D.fun;
+
+        let wei = derivative(&curve_get_D, &samples, Step::Wei);
+        assert_eq!(wei.len(), samples.len());
+        let (s, slope) = (&samples[3], &wei[3].1);
+        let expected = u256_to_bigint(f(s.x_raw + 1)) - u256_to_bigint(f(s.x_raw));
+        assert_eq!(*slope, Rational::from_integer(expected));
+
+        // D grows by about as much as the balance does, give or take the curve's shape
+        let spacing = derivative(&curve_get_D, &samples, Step::Spacing);
+        assert_eq!(spacing.len(), samples.len() - 1);
+        for (_, slope) in &spacing {
+            let slope = to_display_units(slope, &curve_get_D);
+            assert!(0.5 < slope && slope < 1.5, "slope = {slope}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use primitive_types::{U256};
+
+/// Counts of values falling into equal-width bins, starting at `lo`
+pub struct Histogram {
+    pub lo: f64,
+    pub bin_width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Bins spanning `[lo, hi)`, with `hi` itself counted in the last bin. Values outside the
+    /// range and non-finite values are left out
+    pub fn over(values: &[f64], lo: f64, hi: f64, bins: usize) -> Self {
+        let bins = bins.max(1);
+        let bin_width = if hi > lo { (hi - lo) / bins as f64 } else { 1.0 };
+        let mut counts = vec![0; bins];
+        for &v in values.iter().filter(|v| v.is_finite() && lo <= **v && **v <= hi) {
+            let i = (((v - lo) / bin_width) as usize).min(bins - 1);
+            counts[i] += 1;
+        }
+        Histogram { lo, bin_width, counts }
+    }
+
+    /// Bins spanning the finite values
+    pub fn new(values: &[f64], bins: usize) -> Self {
+        let finite = values.iter().copied().filter(|v| v.is_finite());
+        let (lo, hi) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        if lo > hi {
+            return Histogram { lo: 0.0, bin_width: 1.0, counts: vec![0; bins.max(1)] };
+        }
+        Histogram::over(values, lo, hi, bins)
+    }
+
+    pub fn bin_centre(&self, i: usize) -> f64 {
+        self.lo + (i as f64 + 0.5) * self.bin_width
+    }
+}
+
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+}
+
+/// Summary statistics of the finite values, or `None` if there are none
+pub fn summarise(values: &[f64]) -> Option<Summary> {
+    let mut finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.is_empty() {
+        return None;
+    }
+    finite.sort_by(f64::total_cmp);
+    let n = finite.len();
+    let mean = finite.iter().sum::<f64>() / n as f64;
+    let median = if n % 2 == 1 { finite[n / 2] } else { (finite[n / 2 - 1] + finite[n / 2]) / 2.0 };
+    let variance = finite.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+    Some(Summary { count: n, mean, median, stddev: variance.sqrt() })
+}
+
+/// `y mod radix^digits` for each output, i.e. its lowest `digits` digits.
+/// Empty if `radix^digits` doesn't fit in a U256
+pub fn residues(ys: impl Iterator<Item = U256>, radix: u8, digits: u32) -> Vec<U256> {
+    let Some(modulus) = U256::from(radix).checked_pow(U256::from(digits)) else { return Vec::new() };
+    ys.map(|y| y % modulus).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maths::u256d;
+
+    #[test]
+    fn test_distribution() -> Result<(), Box<dyn std::error::Error>> {
+        let values = [0.0, 0.0, 4.0, 4.0, f64::INFINITY];
+        let summary = summarise(&values).unwrap();
+        assert_eq!((summary.count, summary.mean, summary.median, summary.stddev), (4, 2.0, 2.0, 2.0));
+
+        let histogram = Histogram::new(&values, 2);
+        assert_eq!(histogram.counts, [2, 2]);
+        assert_eq!(histogram.bin_centre(0), 1.0);
+        assert_eq!(Histogram::over(&values, 0.0, 8.0, 4).counts, [2, 0, 2, 0]);
+
+        let ys = ["1234", "5670", "99"].into_iter().map(u256d);
+        assert_eq!(residues(ys, 10, 2), [u256d("34"), u256d("70"), u256d("99")]);
+        Ok(())
+    }
+}
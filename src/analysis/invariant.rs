@@ -0,0 +1,103 @@
+use primitive_types::{U256};
+
+use crate::expr::{self, Expr};
+use crate::sampling::Sample;
+
+/// A condition expected to hold at every sample, written in the expression language over `x`, `y`
+/// and the other variables of `Sample::var`
+pub struct Invariant {
+    pub source: String,
+    pub expr: Result<Expr, String>,
+}
+
+impl Invariant {
+    pub fn new(source: &str) -> Self {
+        Invariant { source: source.to_string(), expr: expr::parse(source) }
+    }
+}
+
+pub struct InvariantViolation {
+    /// Index of the violated invariant
+    pub invariant: usize,
+    pub x: f64,
+    pub x_raw: U256,
+    pub y: f64,
+    pub y_raw: U256,
+}
+
+/// Evaluates every invariant at every sample that produced a value.
+/// Returns the violations and, per invariant, how many samples it could not be evaluated at
+/// (e.g. because of a division by zero or a variable that isn't available)
+pub fn check_invariants(invariants: &[Invariant], samples: &[Sample]) -> (Vec<InvariantViolation>, Vec<usize>) {
+    let mut violations = Vec::new();
+    let mut errors = vec![0; invariants.len()];
+    for s in samples {
+        let (Some(y), Some(y_raw)) = (s.y, s.y_raw) else { continue };
+        for (i, invariant) in invariants.iter().enumerate() {
+            let Ok(expr) = &invariant.expr else { continue };
+            match expr.holds(&|name| s.var(name)) {
+                Ok(true) => {}
+                Ok(false) => violations.push(InvariantViolation { invariant: i, x: s.x, x_raw: s.x_raw, y, y_raw }),
+                Err(_) => errors[i] += 1,
+            }
+        }
+    }
+    (violations, errors)
+}
+
+/// Removes invariant `i` along with its violations and error count, and renumbers the violations of
+/// the invariants after it
+pub fn remove_invariant(
+    invariants: &mut Vec<Invariant>,
+    violations: &mut Vec<InvariantViolation>,
+    errors: &mut Vec<usize>,
+    i: usize,
+) {
+    invariants.remove(i);
+    violations.retain(|v| v.invariant != i);
+    for v in violations.iter_mut().filter(|v| v.invariant > i) {
+        v.invariant -= 1;
+    }
+    if i < errors.len() {
+        errors.remove(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions;
+    use crate::sampling::{sample_curve_u256_safe, SampleOptions};
+
+    #[test]
+    fn test_declared_invariants_hold() -> Result<(), Box<dyn std::error::Error>> {
+        for fun in functions::ALL {
+            let invariants: Vec<Invariant> = fun.invariants.iter().map(|src| Invariant::new(src)).collect();
+            assert!(invariants.iter().all(|i| i.expr.is_ok()), "{} has an invariant that doesn't parse", fun.name);
+
+            let (xb, np) = (&fun.x_bounds, &fun.num_points);
+            let samples = sample_curve_u256_safe(fun, &SampleOptions::default(), np.default, xb.min, xb.max);
+            let (violations, errors) = check_invariants(&invariants, &samples);
+            assert!(violations.is_empty(), "{} violates {}", fun.name, invariants[violations[0].invariant].source);
+            assert!(errors.iter().all(|&e| e == 0));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_invariant() -> Result<(), Box<dyn std::error::Error>> {
+        let fun = &functions::mul_by_inverse::x_mul_inverse;
+        let mut invariants: Vec<Invariant> = ["y == 0", "y <= x", "x == 0"].iter().map(|src| Invariant::new(src)).collect();
+        let samples = sample_curve_u256_safe(fun, &SampleOptions::default(), 20, 0.5, 2.0);
+        let (mut violations, mut errors) = check_invariants(&invariants, &samples);
+        assert!(violations.iter().any(|v| v.invariant == 0) && violations.iter().any(|v| v.invariant == 2));
+
+        let last_violations = violations.iter().filter(|v| v.invariant == 2).count();
+        remove_invariant(&mut invariants, &mut violations, &mut errors, 0);
+        assert_eq!(invariants.len(), 2);
+        assert_eq!(errors.len(), 2);
+        assert!(violations.iter().all(|v| v.invariant < invariants.len()));
+        assert_eq!(violations.iter().filter(|v| v.invariant == 1).count(), last_violations);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use primitive_types::{U256};
+
+use crate::maths::*;
+use crate::sampling::{eval_or_revert, with_silent_panics};
+
+/// The sequence `x_{n+1} = f(x_n)` from a start value
+pub struct Orbit {
+    /// `x_0, x_1, ...` in raw units
+    pub values: Vec<U256>,
+    /// The panic message if the function reverted at the last value
+    pub revert: Option<String>,
+}
+
+impl Orbit {
+    /// The step from which the sequence stays put, if it reached a fixed point
+    pub fn fixed_point_step(&self) -> Option<usize> {
+        let n = self.values.len();
+        (n >= 2 && self.values[n - 1] == self.values[n - 2]).then_some(n - 2)
+    }
+}
+
+/// Applies `fun` up to `max_steps` times from `start`, stopping early at a revert or a fixed point.
+/// Only meaningful for functions whose output has the same format as their input
+pub fn iterate(fun: &FixedPointFunction, start: U256, max_steps: usize) -> Orbit {
+    let mut values = vec![start];
+    let mut revert = None;
+    with_silent_panics(|| {
+        for _ in 0..max_steps {
+            let x = *values.last().unwrap();
+            match eval_or_revert(fun, x) {
+                Ok(y) => {
+                    values.push(y);
+                    if y == x { break; }
+                }
+                Err(message) => {
+                    revert = Some(message);
+                    break;
+                }
+            }
+        }
+    });
+    Orbit { values, revert }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+
+    #[test]
+    fn test_iterate() -> Result<(), Box<dyn std::error::Error>> {
+        // x * (1/x) rounds down until it lands on a value it maps to itself
+        let orbit = iterate(&x_mul_inverse, u256d("3"), 100);
+        assert!(orbit.revert.is_none());
+        let step = orbit.fixed_point_step().unwrap();
+        let fixed = orbit.values[step];
+        assert_eq!((x_mul_inverse.fun)(fixed), fixed);
+        assert!(orbit.values.windows(2).all(|w| (x_mul_inverse.fun)(w[0]) == w[1]));
+
+        let orbit = iterate(&x_mul_inverse, u256d("600000000000000000000000000000000000"), 100);
+        assert_eq!(orbit.values.len(), 1);
+        assert_eq!(orbit.revert.as_deref(), Some("error"));
+        Ok(())
+    }
+}
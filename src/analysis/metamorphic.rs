@@ -0,0 +1,84 @@
+use num_bigint::BigInt;
+use num_traits::Signed;
+use primitive_types::{U256};
+
+use crate::maths::*;
+use crate::rational::u256_to_bigint;
+use crate::sampling::eval;
+
+/*
+ * Metamorphic relations: properties linking the function's value at a sample to its value at
+ * inputs derived from it, for functions that have no reference to compare against.
+ * Each relation yields a signed deviation per sample, in raw output units.
+ */
+pub enum Relation {
+    /// `f(k*x)` against `k*f(x)`, violated when they differ by more than `tolerance`
+    Scaling { k: U256, tolerance: BigInt },
+    /// `f(x+1) - f(x)`, violated outside `[min, max]`
+    Increment { min: BigInt, max: BigInt },
+    /// `g(f(x))` against `x` for an inverse `g`, violated when they differ by more than `tolerance`
+    Inverse { inverse: &'static FixedPointFunction, tolerance: BigInt },
+}
+
+impl Relation {
+    pub fn label(&self) -> String {
+        match self {
+            Relation::Scaling { k, .. } => format!("f({k}x) - {k}f(x)"),
+            Relation::Increment { .. } => "f(x+1) - f(x)".to_string(),
+            Relation::Inverse { inverse, .. } => format!("{}(f(x)) - x", inverse.name),
+        }
+    }
+
+    /// The deviation at `x`, where `y = f(x)`, evaluating `fun` (or the inverse) at the derived input.
+    /// `None` where a derived input overflows or an evaluation reverts.
+    /// The caller is expected to have silenced the panic hook
+    pub fn deviation(&self, fun: &FixedPointFunction, x: U256, y: U256) -> Option<BigInt> {
+        match self {
+            Relation::Scaling { k, .. } => {
+                let y_kx = eval(fun, x.checked_mul(*k)?)?;
+                Some(u256_to_bigint(y_kx) - u256_to_bigint(*k) * u256_to_bigint(y))
+            }
+            Relation::Increment { .. } => {
+                let y_next = eval(fun, x.checked_add(U256::one())?)?;
+                Some(u256_to_bigint(y_next) - u256_to_bigint(y))
+            }
+            Relation::Inverse { inverse, .. } => {
+                let round_trip = eval(inverse, y)?;
+                Some(u256_to_bigint(round_trip) - u256_to_bigint(x))
+            }
+        }
+    }
+
+    pub fn is_violated(&self, deviation: &BigInt) -> bool {
+        match self {
+            Relation::Scaling { tolerance, .. } | Relation::Inverse { tolerance, .. } => &deviation.abs() > tolerance,
+            Relation::Increment { min, max } => deviation < min || deviation > max,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+    use crate::sampling::with_silent_panics;
+
+    #[test]
+    fn test_deviation() -> Result<(), Box<dyn std::error::Error>> {
+        let x = u256d("3000000000000000000");
+        let y = (x_mul_inverse.fun)(x);
+
+        let increment = Relation::Increment { min: BigInt::from(0), max: BigInt::from(0) };
+        let deviation = with_silent_panics(|| increment.deviation(&x_mul_inverse, x, y)).unwrap();
+        assert_eq!(deviation, u256_to_bigint((x_mul_inverse.fun)(x + 1)) - u256_to_bigint(y));
+
+        // Scaling the input past the revert guard leaves the relation undefined
+        let scaling = Relation::Scaling { k: u256d("1000000000000000000"), tolerance: BigInt::from(0) };
+        assert!(with_silent_panics(|| scaling.deviation(&x_mul_inverse, x, y)).is_none());
+
+        let scaling = Relation::Scaling { k: u256d("2"), tolerance: BigInt::from(1) };
+        let deviation = with_silent_panics(|| scaling.deviation(&x_mul_inverse, x, y)).unwrap();
+        assert_eq!(scaling.is_violated(&deviation), deviation.abs() > BigInt::from(1));
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use primitive_types::{U256};
+
+use crate::maths::*;
+use crate::sampling::{eval, with_silent_panics, Sample};
+
+/// Two inputs `x0 < x1` whose outputs break the expected monotonicity
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub x0: U256,
+    pub y0: U256,
+    pub x1: U256,
+    pub y1: U256,
+}
+
+impl Violation {
+    pub fn is_adjacent(&self) -> bool {
+        self.x1 - self.x0 == U256::one()
+    }
+}
+
+/// Checks each pair of neighbouring samples that produced a value, skipping over reverts
+pub fn scan_samples(monotonicity: Monotonicity, samples: &[Sample]) -> Vec<Violation> {
+    let valid: Vec<(U256, U256)> = samples.iter()
+        .filter_map(|s| s.y_raw.map(|y| (s.x_raw, y)))
+        .collect();
+    valid.windows(2)
+        .filter(|w| w[0].0 < w[1].0 && !monotonicity.holds(w[0].1, w[1].1))
+        .map(|w| Violation { x0: w[0].0, y0: w[0].1, x1: w[1].0, y1: w[1].1 })
+        .collect()
+}
+
+/// Evaluates every integer within `radius` of each sample and checks the adjacent pairs
+pub fn scan_neighbourhoods(fun: &FixedPointFunction, monotonicity: Monotonicity, samples: &[Sample], radius: u64) -> Vec<Violation> {
+    let radius = U256::from(radius);
+    let mut violations = Vec::new();
+    with_silent_panics(|| {
+        for s in samples {
+            let start = s.x_raw.saturating_sub(radius);
+            let end = s.x_raw.saturating_add(radius);
+            let mut prev: Option<(U256, U256)> = None;
+            let mut x = start;
+            loop {
+                if let Some(y) = eval(fun, x) {
+                    if let Some((x0, y0)) = prev && !monotonicity.holds(y0, y) {
+                        violations.push(Violation { x0, y0, x1: x, y1: y });
+                    }
+                    prev = Some((x, y));
+                } else {
+                    prev = None;
+                }
+                if x >= end { break; }
+                x += U256::one();
+            }
+        }
+    });
+    violations.sort_by_key(|v| (v.x0, v.x1));
+    violations.dedup_by_key(|v| (v.x0, v.x1));
+    violations
+}
+
+/// Bisects a violation down to adjacent integers `x, x + 1` that still violate the monotonicity.
+/// One half of any violating range always violates too, unless the function reverts at the
+/// midpoint, in which case the narrowest violation found so far is returned
+pub fn minimise(fun: &FixedPointFunction, monotonicity: Monotonicity, violation: &Violation) -> Violation {
+    let mut v = violation.clone();
+    with_silent_panics(|| {
+        while !v.is_adjacent() {
+            let mid = v.x0 + (v.x1 - v.x0) / 2;
+            let Some(y_mid) = eval(fun, mid) else { break };
+            if !monotonicity.holds(v.y0, y_mid) {
+                (v.x1, v.y1) = (mid, y_mid);
+            } else {
+                (v.x0, v.y0) = (mid, y_mid);
+            }
+        }
+    });
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+
+    #[test]
+    fn test_minimise() -> Result<(), Box<dyn std::error::Error>> {
+        // x * (1/x) rounds down by varying amounts, so it is not monotonic
+        let increasing = Monotonicity { direction: Direction::Increasing, strict: false };
+        let x0 = u256d("1000000000000000000");
+        let x1 = u256d("3000000000000000000");
+        let (y0, y1) = ((x_mul_inverse.fun)(x0), (x_mul_inverse.fun)(x1));
+        assert!(!increasing.holds(y0, y1));
+
+        let v = minimise(&x_mul_inverse, increasing, &Violation { x0, y0, x1, y1 });
+        assert!(v.is_adjacent());
+        assert!(x0 <= v.x0 && v.x1 <= x1);
+        assert!(v.y0 > v.y1);
+        assert_eq!((v.y0, v.y1), ((x_mul_inverse.fun)(v.x0), (x_mul_inverse.fun)(v.x1)));
+        Ok(())
+    }
+}
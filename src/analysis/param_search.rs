@@ -0,0 +1,139 @@
+use num_traits::ToPrimitive;
+use primitive_types::{U256};
+
+use crate::analysis::monotonic;
+use crate::analysis::profit::{self, RoundTrip};
+use crate::maths::*;
+use crate::params::{self, Parameter};
+use crate::reference::*;
+use crate::sampling::{sample_curve_u256_safe, Sample, SampleOptions};
+use crate::search::Rng;
+
+/*
+ * Search over a function's parameters for the combinations where it behaves worst over a range of
+ * inputs. Each combination is scored by sampling the curve with the parameters set.
+ */
+
+pub enum ParamObjective {
+    /// Largest magnitude of the error against the reference, in the given unit
+    MaxError(ErrorUnit),
+    /// Number of reverting samples
+    Reverts,
+    /// Number of neighbouring sample pairs breaking the monotonicity
+    NonMonotonic(Monotonicity),
+    /// Largest profit of the round trip through an inverse, which reads the same parameters
+    Profit(RoundTrip),
+}
+
+impl ParamObjective {
+    fn score(&self, fun: &FixedPointFunction, samples: &[Sample]) -> f64 {
+        match self {
+            ParamObjective::MaxError(unit) => samples.iter()
+                .filter_map(|s| Some(error_in(*unit, s.y_raw?, s.reference.as_ref()?, &fun.y_bounds).abs()))
+                .filter(|err| !err.is_nan())
+                .fold(0.0, f64::max),
+            ParamObjective::Reverts => samples.iter().filter(|s| s.y.is_none()).count() as f64,
+            ParamObjective::NonMonotonic(monotonicity) => monotonic::scan_samples(*monotonicity, samples).len() as f64,
+            ParamObjective::Profit(round_trip) => profit::profit_series(round_trip, samples).iter()
+                .filter_map(|(_, profit)| profit.to_f64())
+                .fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+pub struct ParamCandidate {
+    /// One value per parameter, in the order the function declares them
+    pub values: Vec<U256>,
+    pub score: f64,
+}
+
+/// `per_axis` values of each parameter across its range, spaced geometrically when the range
+/// starts above zero, combined in every way
+pub fn grid(params: &[Parameter], per_axis: usize) -> Vec<Vec<U256>> {
+    let per_axis = per_axis.max(2);
+    let mut combinations: Vec<Vec<U256>> = vec![Vec::new()];
+    for p in params {
+        let (lo, hi) = p.range();
+        let (lo_f, hi_f) = (u256_to_f64(lo, 10, 0), u256_to_f64(hi, 10, 0));
+        let axis: Vec<U256> = (0..per_axis)
+            .map(|i| {
+                // The ends are exact, the values between them only as exact as an f64
+                if i == 0 { return lo; }
+                if i == per_axis - 1 { return hi; }
+                let t = i as f64 / (per_axis - 1) as f64;
+                let value = if lo_f > 0.0 { lo_f * (hi_f / lo_f).powf(t) } else { lo_f + t * (hi_f - lo_f) };
+                f64_to_u256(value, 10, 0).clamp(lo, hi)
+            })
+            .collect();
+        combinations = combinations.into_iter()
+            .flat_map(|c| axis.iter().map(move |&v| [c.clone(), vec![v]].concat()))
+            .collect();
+    }
+    combinations
+}
+
+/// `count` random combinations, each parameter drawn from its range
+pub fn random(params: &[Parameter], count: usize, rng: &mut Rng) -> Vec<Vec<U256>> {
+    (0..count)
+        .map(|_| params.iter().map(|p| { let (lo, hi) = p.range(); rng.in_range(lo, hi) }).collect())
+        .collect()
+}
+
+/// Scores every combination by sampling `num_points` inputs in `[x_min, x_max]` (display units)
+/// with the parameters set, and returns the `top_k` worst, worst first
+pub fn rank(
+    fun: &FixedPointFunction,
+    objective: ParamObjective,
+    candidates: Vec<Vec<U256>>,
+    x_min: f64,
+    x_max: f64,
+    num_points: usize,
+    top_k: usize,
+) -> Vec<ParamCandidate> {
+    let options = SampleOptions { reference: matches!(objective, ParamObjective::MaxError(_)), ..Default::default() };
+    let mut ranked: Vec<ParamCandidate> = candidates.into_iter()
+        .map(|values| {
+            let score = params::with_values(fun.params, &values, || {
+                objective.score(fun, &sample_curve_u256_safe(fun, &options, num_points, x_min, x_max))
+            });
+            ParamCandidate { values, score }
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked.truncate(top_k);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parse;
+    use crate::functions::curve::{curve_get_D, curve_get_y};
+
+    #[test]
+    fn test_rank() -> Result<(), Box<dyn std::error::Error>> {
+        let combinations = grid(curve_get_D.params, 3);
+        assert_eq!(combinations.len(), 9);
+        assert!(combinations.iter().all(|c| c.len() == 2));
+        for (p, i) in curve_get_D.params.iter().zip(0..) {
+            let (lo, hi) = p.range();
+            assert!(combinations.iter().any(|c| c[i] == lo) && combinations.iter().any(|c| c[i] == hi));
+        }
+
+        let ranked = rank(&curve_get_D, ParamObjective::MaxError(ErrorUnit::Ulp), combinations, 0.5, 2.0, 5, 4);
+        assert_eq!(ranked.len(), 4);
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+
+        // Parameters set for the search don't leak into later evaluations
+        assert_eq!(params::get(&curve_get_D.params[0]), curve_get_D.params[0].default_value());
+
+        // The inverse reads the same parameters, so each round trip runs on one pool
+        let round_trip = RoundTrip { inverse: &curve_get_y, profit: parse("z - x")? };
+        let ranked = rank(&curve_get_D, ParamObjective::Profit(round_trip), grid(curve_get_D.params, 2), 0.5, 2.0, 5, 4);
+        assert_eq!(ranked.len(), 4);
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+        // The pool rounds in its favour at the corners of parameter space too
+        assert!(ranked.iter().all(|c| c.score <= 0.0));
+        Ok(())
+    }
+}
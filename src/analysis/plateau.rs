@@ -0,0 +1,87 @@
+use primitive_types::{U256};
+
+use crate::analysis::bisect;
+use crate::maths::*;
+use crate::sampling::{eval, with_silent_panics, Sample};
+
+/// A range of inputs `[start, end]` that all give the same output
+pub struct Plateau {
+    pub start: U256,
+    pub end: U256,
+    pub y: U256,
+    /// Whether `start` is an exact step boundary, rather than the first sample because nothing
+    /// was sampled below it
+    pub start_exact: bool,
+    /// Whether `end` is an exact step boundary, rather than the last sample
+    pub end_exact: bool,
+}
+
+impl Plateau {
+    /// Number of inputs on the plateau
+    pub fn width(&self) -> U256 {
+        self.end - self.start + 1
+    }
+}
+
+/// Finds every run of two or more neighbouring samples with the same output, then bisects towards
+/// the neighbouring samples on either side for the exact inputs where the output changes.
+/// Returns the plateaus in increasing order of input.
+/// If the output leaves and comes back to the plateau's value between two samples, the bisection
+/// finds one of the changes, so the plateau may be reported narrower or wider than it is
+pub fn detect_plateaus(fun: &FixedPointFunction, samples: &[Sample]) -> Vec<Plateau> {
+    let valid: Vec<(U256, Option<U256>)> = samples.iter().map(|s| (s.x_raw, s.y_raw)).collect();
+    let mut plateaus = Vec::new();
+    with_silent_panics(|| {
+        let mut i = 0;
+        while i < valid.len() {
+            let (first, Some(y)) = valid[i] else { i += 1; continue };
+            let mut j = i;
+            while j + 1 < valid.len() && valid[j + 1].1 == Some(y) && valid[j + 1].0 > valid[j].0 {
+                j += 1;
+            }
+            if j > i {
+                let last = valid[j].0;
+                let on_plateau = |x: U256| eval(fun, x) == Some(y);
+                let (start, start_exact) = match i.checked_sub(1).map(|k| valid[k].0) {
+                    Some(prev) if prev < first => (bisect(prev, first, on_plateau).1, true),
+                    _ => (first, false),
+                };
+                let (end, end_exact) = match valid.get(j + 1).map(|next| next.0) {
+                    Some(next) if last < next => (bisect(last, next, |x| !on_plateau(x)).0, true),
+                    _ => (last, false),
+                };
+                plateaus.push(Plateau { start, end, y, start_exact, end_exact });
+            }
+            i = j + 1;
+        }
+    });
+    plateaus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+    use crate::sampling::{sample_curve_u256_safe, SampleOptions};
+
+    #[test]
+    fn test_detect_plateaus() -> Result<(), Box<dyn std::error::Error>> {
+        // x * (1/x) only changes where the rounded inverse steps, so neighbouring samples share outputs
+        let f = x_mul_inverse.fun;
+        let samples = sample_curve_u256_safe(&x_mul_inverse, &SampleOptions::default(), 20, 0.5, 100.0);
+        let plateaus = detect_plateaus(&x_mul_inverse, &samples);
+        assert!(!plateaus.is_empty());
+        assert!(plateaus.windows(2).all(|w| w[0].end < w[1].start));
+
+        for p in &plateaus {
+            assert_eq!((f(p.start), f(p.end)), (p.y, p.y));
+            if p.start_exact {
+                assert_ne!(f(p.start - 1), p.y);
+            }
+            if p.end_exact {
+                assert_ne!(f(p.end + 1), p.y);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use num_bigint::BigInt;
+use primitive_types::{U256};
+
+use crate::expr::Expr;
+use crate::maths::*;
+use crate::rational::u256_to_bigint;
+use crate::sampling::{eval, with_silent_panics, Sample};
+
+/// An operation followed by its inverse, e.g. a deposit of `x` assets for `y = f(x)` shares and a
+/// withdrawal of those shares for `z = g(y)` assets, with the profit of doing both
+pub struct RoundTrip {
+    pub inverse: &'static FixedPointFunction,
+    /// An expression over `x`, `y` and `z` in raw units, e.g. `z - x`
+    pub profit: Expr,
+}
+
+impl RoundTrip {
+    /// `z` and the profit of the round trip starting at `x`, where `y = f(x)`. `None` if the
+    /// inverse reverts or the profit can't be evaluated.
+    /// The caller is expected to have silenced the panic hook
+    pub fn run(&self, x: U256, y: U256) -> Option<(U256, BigInt)> {
+        let z = eval(self.inverse, y)?;
+        let env = |name: &str| match name {
+            "x" => Some(u256_to_bigint(x)),
+            "y" => Some(u256_to_bigint(y)),
+            "z" => Some(u256_to_bigint(z)),
+            _ => None,
+        };
+        self.profit.eval(&env).ok().map(|profit| (z, profit))
+    }
+}
+
+/// The round trip's profit at every sample that produced a value
+pub fn profit_series(round_trip: &RoundTrip, samples: &[Sample]) -> Vec<(f64, BigInt)> {
+    with_silent_panics(|| {
+        samples.iter()
+            .filter_map(|s| round_trip.run(s.x_raw, s.y_raw?).map(|(_, profit)| (s.x, profit)))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parse;
+    use crate::functions::curve::{curve_get_D, curve_get_y};
+
+    #[test]
+    fn test_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let round_trip = RoundTrip { inverse: &curve_get_y, profit: parse("z - x")? };
+        let x = u256d("42000000000000000000");
+        let y = (curve_get_D.fun)(x);
+        let (z, profit) = with_silent_panics(|| round_trip.run(x, y)).unwrap();
+        assert_eq!(z, (curve_get_y.fun)(y));
+        assert_eq!(profit, u256_to_bigint(z) - u256_to_bigint(x));
+
+        let unknown = RoundTrip { inverse: &curve_get_y, profit: parse("z - w")? };
+        assert!(with_silent_panics(|| unknown.run(x, y)).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use primitive_types::{U256};
+
+use crate::maths::*;
+use crate::rational::Rational;
+use crate::reference::raw_error;
+use crate::sampling::Sample;
+
+/// A sample whose result is on the wrong side of the reference for the expected rounding
+pub struct WrongRounding {
+    pub x: f64,
+    pub x_raw: U256,
+    pub y: f64,
+    /// How far past the reference the result went, in raw output units. Always positive
+    pub amount: Rational,
+}
+
+/// How far `y_raw` rounded past `reference` in the wrong direction, or `None` if it rounded the
+/// right way (or is exact)
+pub fn wrong_direction_amount(rounding: Rounding, y_raw: U256, reference: &Rational) -> Option<Rational> {
+    let err = raw_error(y_raw, reference);
+    let amount = match rounding {
+        Rounding::Down => err,
+        Rounding::Up => -&err,
+    };
+    (!amount.is_negative() && !amount.is_zero()).then_some(amount)
+}
+
+/// Every sample with a value and a reference that rounded the wrong way
+pub fn check_rounding(rounding: Rounding, samples: &[Sample]) -> Vec<WrongRounding> {
+    samples.iter()
+        .filter_map(|s| {
+            let (y, y_raw, reference) = (s.y?, s.y_raw?, s.reference.as_ref()?);
+            let amount = wrong_direction_amount(rounding, y_raw, reference)?;
+            Some(WrongRounding { x: s.x, x_raw: s.x_raw, y, amount })
+        })
+        .collect()
+}
+
+/// Sum of the wrong-direction amounts, in raw output units
+pub fn total_amount(wrong: &[WrongRounding]) -> Rational {
+    wrong.iter().fold(Rational::from_u256(U256::zero()), |total, w| &total + &w.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+
+    #[test]
+    fn test_wrong_direction_amount() -> Result<(), Box<dyn std::error::Error>> {
+        let reference = Rational::new(BigInt::from(21), BigInt::from(2));
+        assert!(wrong_direction_amount(Rounding::Down, u256d("10"), &reference).is_none());
+        assert_eq!(wrong_direction_amount(Rounding::Down, u256d("11"), &reference), Some(Rational::new(BigInt::from(1), BigInt::from(2))));
+        assert_eq!(wrong_direction_amount(Rounding::Up, u256d("10"), &reference), Some(Rational::new(BigInt::from(1), BigInt::from(2))));
+        assert!(wrong_direction_amount(Rounding::Up, u256d("11"), &reference).is_none());
+
+        // An exact result rounds neither way
+        let exact = Rational::from_u256(u256d("10"));
+        assert!(wrong_direction_amount(Rounding::Down, u256d("10"), &exact).is_none());
+        assert!(wrong_direction_amount(Rounding::Up, u256d("10"), &exact).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use primitive_types::{U256};
+
+use crate::sampling::Sample;
+
+/// Summary of one sampling pass
+pub struct SampleStats {
+    pub num_samples: usize,
+    /// Number of reverting samples per panic message
+    pub reverts: BTreeMap<String, usize>,
+    /// Smallest output and the first input giving it, as `(x, y)` in raw units
+    pub min_y: Option<(U256, U256)>,
+    /// Largest output and the first input giving it
+    pub max_y: Option<(U256, U256)>,
+    /// Number of different raw inputs evaluated, which can be fewer than the samples when zoomed
+    /// in far enough that neighbouring display values round to the same input
+    pub distinct_inputs: usize,
+    pub sampling_time: Duration,
+}
+
+impl SampleStats {
+    pub fn new(samples: &[Sample], sampling_time: Duration) -> Self {
+        let mut reverts = BTreeMap::new();
+        for message in samples.iter().filter_map(|s| s.revert.as_ref()) {
+            *reverts.entry(message.clone()).or_insert(0) += 1;
+        }
+        let values = || samples.iter().filter_map(|s| s.y_raw.map(|y| (s.x_raw, y)));
+        // `min_by_key` and `max_by_key` pick the first and last of equal elements respectively
+        let min_y = values().min_by_key(|&(_, y)| y);
+        let max_y = values().rev().max_by_key(|&(_, y)| y);
+        let distinct_inputs = samples.iter().map(|s| s.x_raw).collect::<HashSet<_>>().len();
+        SampleStats { num_samples: samples.len(), reverts, min_y, max_y, distinct_inputs, sampling_time }
+    }
+
+    pub fn num_reverts(&self) -> usize {
+        self.reverts.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+    use crate::maths::u256d;
+    use crate::sampling::{sample_curve_u256_safe, SampleOptions};
+
+    #[test]
+    fn test_sample_stats() -> Result<(), Box<dyn std::error::Error>> {
+        // x_mul_inverse reverts above 5e35 raw, i.e. 5e17 in display units
+        let samples = sample_curve_u256_safe(&x_mul_inverse, &SampleOptions::default(), 4, 0.0, 9e17);
+        let stats = SampleStats::new(&samples, Duration::ZERO);
+        assert_eq!(stats.num_samples, 4);
+        assert_eq!(stats.reverts.get("error"), Some(&2));
+        assert_eq!(stats.num_reverts(), 2);
+        assert_eq!(stats.distinct_inputs, 4);
+        assert_eq!(stats.min_y, Some((U256::zero(), U256::zero())));
+        let (x, y) = stats.max_y.unwrap();
+        assert_eq!((x_mul_inverse.fun)(x), y);
+        assert!(y <= u256d("1000000000000000000"));
+        Ok(())
+    }
+}
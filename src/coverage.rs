@@ -0,0 +1,55 @@
+use std::cell::Cell;
+
+/*
+ * Records which code path a function took.
+ *
+ * A function lists the names of its paths in `FixedPointFunction::paths` and calls `mark` with the
+ * index of a path when it takes it. When several paths are marked during one evaluation the last
+ * one wins, so marking just before each `return` (or `panic!`) identifies how the result was reached.
+ */
+
+thread_local! {
+    static PATH_TAKEN: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+pub fn mark(path: usize) {
+    PATH_TAKEN.with(|p| p.set(Some(path)));
+}
+
+pub fn reset() {
+    PATH_TAKEN.with(|p| p.set(None));
+}
+
+pub fn path_taken() -> Option<usize> {
+    PATH_TAKEN.with(|p| p.get())
+}
+
+/// Indices of the declared paths that none of `taken` hit
+pub fn paths_not_hit(num_paths: usize, taken: impl Iterator<Item = Option<usize>>) -> Vec<usize> {
+    let mut hit = vec![false; num_paths];
+    for path in taken.flatten() {
+        if path < num_paths {
+            hit[path] = true;
+        }
+    }
+    (0..num_paths).filter(|&p| !hit[p]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_not_hit() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(paths_not_hit(4, [Some(1), None, Some(3), Some(1)].into_iter()), vec![0, 2]);
+        // Paths beyond the declared ones are ignored
+        assert_eq!(paths_not_hit(2, [Some(5)].into_iter()), vec![0, 1]);
+        assert!(paths_not_hit(0, std::iter::empty()).is_empty());
+
+        reset();
+        mark(2);
+        mark(0);
+        assert_eq!(path_taken(), Some(0));
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+/*
+ * A small expression language over exact integers, used for user-defined objectives and invariants.
+ *
+ *     pred    := or ("implies" pred)?
+ *     or      := and ("or" and)*
+ *     and     := not ("and" not)*
+ *     not     := "not" not | cmp
+ *     cmp     := expr (("<" | "<=" | ">" | ">=" | "==" | "!=") expr)?
+ *     expr    := term (("+" | "-") term)*
+ *     term    := unary (("*" | "/" | "%") unary)*
+ *     unary   := "-" unary | power
+ *     power   := atom ("^" unary)?
+ *     atom    := number | name | name "(" expr ("," expr)* ")" | "(" pred ")"
+ *
+ * Numbers are integers and may use an exponent, so `1e18` is exactly 10^18. Names are looked up in
+ * an environment supplied at evaluation time (e.g. `x` and `y` in raw units). The functions are
+ * `abs`, `min` and `max`. Division truncates towards zero, like the EVM's SDIV. Comparisons and the
+ * logical operators produce booleans, which `holds` evaluates, while `eval` expects a number.
+ */
+
+#[derive(Clone, Debug)]
+pub enum Expr {
+    Num(BigInt),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Compare(CmpOp, Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Logic(LogicOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LogicOp {
+    And,
+    Or,
+    Implies,
+}
+
+enum Value {
+    Int(BigInt),
+    Bool(bool),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(BigInt),
+    Name(String),
+    Sym(&'static str),
+}
+
+const SYMBOLS: [&str; 15] = ["+", "-", "*", "/", "%", "^", "(", ")", ",", "<", "<=", ">", ">=", "==", "!="];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') { i += 1; }
+            let digits: String = chars[start..i].iter().filter(|&&c| c != '_').collect();
+            let mut value: BigInt = digits.parse().map_err(|_| format!("bad number '{digits}'"))?;
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let exp_start = i + 1;
+                i = exp_start;
+                while i < chars.len() && chars[i].is_ascii_digit() { i += 1; }
+                let exp: u32 = chars[exp_start..i].iter().collect::<String>().parse()
+                    .map_err(|_| "bad exponent".to_string())?;
+                value *= BigInt::from(10).pow(exp);
+            }
+            tokens.push(Token::Num(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') { i += 1; }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let sym = SYMBOLS.iter()
+                .filter(|sym| rest.starts_with(**sym))
+                .max_by_key(|sym| sym.len())
+                .ok_or_else(|| format!("unexpected '{c}'"))?;
+            i += sym.chars().count();
+            tokens.push(Token::Sym(sym));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_sym(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Sym(sym)) => Some(sym),
+            _ => None,
+        }
+    }
+
+    fn eat(&mut self, sym: &str) -> bool {
+        if self.peek_sym() == Some(sym) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), String> {
+        if self.eat(sym) { Ok(()) } else { Err(format!("expected '{sym}'")) }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Name(name)) if name == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pred(&mut self) -> Result<Expr, String> {
+        let lhs = self.or()?;
+        if self.eat_keyword("implies") {
+            return Ok(Expr::Logic(LogicOp::Implies, Box::new(lhs), Box::new(self.pred()?)));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat_keyword("or") {
+            lhs = Expr::Logic(LogicOp::Or, Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.not()?;
+        while self.eat_keyword("and") {
+            lhs = Expr::Logic(LogicOp::And, Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.cmp()
+    }
+
+    fn cmp(&mut self) -> Result<Expr, String> {
+        let lhs = self.expr()?;
+        let ops = [("<=", CmpOp::Le), (">=", CmpOp::Ge), ("==", CmpOp::Eq), ("!=", CmpOp::Ne), ("<", CmpOp::Lt), (">", CmpOp::Gt)];
+        for (sym, op) in ops {
+            if self.eat(sym) {
+                return Ok(Expr::Compare(op, Box::new(lhs), Box::new(self.expr()?)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat("+") { BinOp::Add } else if self.eat("-") { BinOp::Sub } else { return Ok(lhs) };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                BinOp::Mul
+            } else if self.eat("/") {
+                BinOp::Div
+            } else if self.eat("%") {
+                BinOp::Rem
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.eat("^") {
+            return Ok(Expr::Binary(BinOp::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("unexpected end of expression")?;
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Name(name) => {
+                if !self.eat("(") {
+                    return Ok(Expr::Var(name));
+                }
+                let mut args = vec![self.expr()?];
+                while self.eat(",") {
+                    args.push(self.expr()?);
+                }
+                self.expect(")")?;
+                Ok(Expr::Call(name, args))
+            }
+            Token::Sym("(") => {
+                let inner = self.pred()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Token::Sym(sym) => Err(format!("unexpected '{sym}'")),
+        }
+    }
+}
+
+pub fn parse(src: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(src)?, pos: 0 };
+    let expr = parser.pred()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {token:?}")),
+    }
+}
+
+/// Evaluates an expression without variables, e.g. `1e18 - 1`
+pub fn parse_constant(src: &str) -> Result<BigInt, String> {
+    parse(src)?.eval(&|_| None)
+}
+
+impl Expr {
+    /// Evaluates a numeric expression, looking names up with `env`
+    pub fn eval(&self, env: &dyn Fn(&str) -> Option<BigInt>) -> Result<BigInt, String> {
+        match self.value(env)? {
+            Value::Int(n) => Ok(n),
+            Value::Bool(_) => Err("expected a number, found a condition".to_string()),
+        }
+    }
+
+    /// Evaluates a condition, looking names up with `env`
+    pub fn holds(&self, env: &dyn Fn(&str) -> Option<BigInt>) -> Result<bool, String> {
+        match self.value(env)? {
+            Value::Bool(b) => Ok(b),
+            Value::Int(_) => Err("expected a condition, found a number".to_string()),
+        }
+    }
+
+    fn value(&self, env: &dyn Fn(&str) -> Option<BigInt>) -> Result<Value, String> {
+        match self {
+            Expr::Num(n) => Ok(Value::Int(n.clone())),
+            Expr::Var(name) => env(name).map(Value::Int).ok_or_else(|| format!("unknown variable '{name}'")),
+            Expr::Neg(inner) => Ok(Value::Int(-inner.eval(env)?)),
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval(env)?, rhs.eval(env)?);
+                let n = match op {
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div | BinOp::Rem if b.is_zero() => return Err("division by zero".to_string()),
+                    BinOp::Div => a / b,
+                    BinOp::Rem => a % b,
+                    BinOp::Pow => {
+                        let exp = b.to_u32().filter(|&e| e <= 1024).ok_or("exponent out of range")?;
+                        a.pow(exp)
+                    }
+                };
+                Ok(Value::Int(n))
+            }
+            Expr::Call(name, args) => {
+                let values = args.iter().map(|a| a.eval(env)).collect::<Result<Vec<_>, _>>()?;
+                let n = match (name.as_str(), values.as_slice()) {
+                    ("abs", [a]) => a.abs(),
+                    ("min", [a, rest @ ..]) => rest.iter().fold(a.clone(), |m, v| m.min(v.clone())),
+                    ("max", [a, rest @ ..]) => rest.iter().fold(a.clone(), |m, v| m.max(v.clone())),
+                    _ => return Err(format!("unknown function {name} with {} arguments", values.len())),
+                };
+                Ok(Value::Int(n))
+            }
+            Expr::Compare(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval(env)?, rhs.eval(env)?);
+                Ok(Value::Bool(match op {
+                    CmpOp::Lt => a < b,
+                    CmpOp::Le => a <= b,
+                    CmpOp::Gt => a > b,
+                    CmpOp::Ge => a >= b,
+                    CmpOp::Eq => a == b,
+                    CmpOp::Ne => a != b,
+                }))
+            }
+            Expr::Not(inner) => Ok(Value::Bool(!inner.holds(env)?)),
+            Expr::Logic(op, lhs, rhs) => {
+                let a = lhs.holds(env)?;
+                Ok(Value::Bool(match op {
+                    LogicOp::And => a && rhs.holds(env)?,
+                    LogicOp::Or => a || rhs.holds(env)?,
+                    LogicOp::Implies => !a || rhs.holds(env)?,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() -> Result<(), Box<dyn std::error::Error>> {
+        let env = |name: &str| match name {
+            "x" => Some(BigInt::from(7)),
+            "y" => Some(BigInt::from(-3)),
+            _ => None,
+        };
+        let eval = |src: &str| parse(src).and_then(|e| e.eval(&env));
+
+        assert_eq!(eval("1e18 - 1")?, BigInt::from(999_999_999_999_999_999u64));
+        assert_eq!(eval("x - 2 * 3 + -y")?, BigInt::from(4));
+        assert_eq!(eval("2 ^ 3 ^ 2")?, BigInt::from(512));
+        assert_eq!(eval("abs(y) + max(x, 10, 2) % 4")?, BigInt::from(5));
+        assert_eq!(eval("(x + 1) / 3")?, BigInt::from(2));
+        assert!(eval("x / (y + 3)").is_err());
+        assert!(eval("z").is_err());
+        assert!(eval("x +").is_err());
+        assert!(eval("x < 1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_holds() -> Result<(), Box<dyn std::error::Error>> {
+        let env = |name: &str| match name {
+            "x" => Some(BigInt::from(500)),
+            "y" => Some(BigInt::from(0)),
+            _ => None,
+        };
+        let holds = |src: &str| parse(src).and_then(|e| e.holds(&env));
+
+        assert!(holds("y <= x")?);
+        assert!(!holds("y >= 1e18 - 1")?);
+        assert!(holds("y == 0 implies x < 1000")?);
+        assert!(!holds("y == 0 implies x < 100 and x != 500")?);
+        assert!(holds("not (x > 1 and y > 1) or y / 0 == 1")?);
+        assert!(holds("x == 1 or x == 2 or (x >= 500)")?);
+        assert!(holds("x + 1").is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,31 @@
-use primitive_types::{U256, U512};
-
 pub mod mul_by_inverse;
 pub mod yearn;
 pub mod curve;
+
+use crate::maths::{FixedPointFunction, FixedPointFunction2, FixedPointFunctionMulti, FixedPointFunctionN};
+
+/* Every function that can be selected for plotting */
+pub const ALL: &[&FixedPointFunction] = &[
+    &mul_by_inverse::x_mul_inverse,
+    &mul_by_inverse::x_mul_inverse_full,
+    &curve::curve_get_D,
+    &curve::curve_get_y,
+    &yearn::yearn_calc_supply,
+];
+
+/* Every two-input function that can be drawn as a heatmap */
+pub const ALL_2: &[&FixedPointFunction2] = &[
+    &curve::curve_get_D_balances,
+];
+
+/* Every function of several inputs, plotted one slice at a time */
+pub const ALL_N: &[&FixedPointFunctionN] = &[
+    &curve::curve_get_D_3,
+];
+
+/* Every function with several outputs */
+pub const ALL_MULTI: &[&FixedPointFunctionMulti] = &[
+    &curve::curve_get_D_iterations,
+    &curve::curve_get_dy,
+    &curve::curve_reserves_after_swap,
+];
@@ -1,6 +1,12 @@
+use num_bigint::BigInt;
+use num_traits::Zero;
 use primitive_types::{U256};
 use crate::vyper::*;
+use crate::gas::{self, branch};
+use crate::coverage::mark;
+use crate::rational::{u256_to_bigint, Rational};
 use crate::maths::*;
+use crate::params::{self, Parameter};
 
 pub const curve_get_D: FixedPointFunction = FixedPointFunction {
     name: "curve_get_D",
@@ -8,34 +14,217 @@ pub const curve_get_D: FixedPointFunction = FixedPointFunction {
     x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0  },
     y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
     num_points: FixedPointNumPoints { default: 100, min: 10, max: 2000 },
+    paths: CURVE_GET_D_PATHS,
+    reference: Some(curve_get_D_ref),
+    monotonicity: Some(Monotonicity { direction: Direction::Increasing, strict: false }),
+    rounding: Some(Rounding::Down),
+    invariants: &["y <= x + balance0 + 1"],
+    params: POOL_PARAMS,
 };
 
-fn curve_get_D_fun(x_n: U256) -> U256 {
-    if x_n == u256d("0") || x_n >= u256d("340282366920938463463374607431768211455") { return x_n; }
-    let _amp = u256d("30000");
-    let A_PRECISION = u256d("100");
+/// Inverse of `curve_get_D`: the balance of the plotted coin that gives the pool invariant `D`
+pub const curve_get_y: FixedPointFunction = FixedPointFunction {
+    name: "curve_get_y_D",
+    fun: curve_get_y_D,
+    x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 200.0 },
+    y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    num_points: FixedPointNumPoints { default: 100, min: 10, max: 2000 },
+    paths: &[
+        "converged, y > y_prev",
+        "converged, y <= y_prev",
+        "revert: did not converge",
+    ],
+    reference: None,
+    monotonicity: Some(Monotonicity { direction: Direction::Increasing, strict: false }),
+    rounding: Some(Rounding::Up),
+    invariants: &[],
+    params: POOL_PARAMS,
+};
+
+/// `curve_get_D` over both balances of the pool, instead of holding coin 0 at `balance0`
+pub const curve_get_D_balances: FixedPointFunction2 = FixedPointFunction2 {
+    name: "curve_get_D(x0, x1)",
+    fun: curve_get_D_balances_fun,
+    x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    z_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 200.0 },
+    num_points: FixedPointNumPoints { default: 50, min: 5, max: 500 },
+    params: &[AMP],
+};
+
+/// `get_D` of a three coin pool over its balances and amplification
+pub const curve_get_D_3: FixedPointFunctionN = FixedPointFunctionN {
+    name: "curve_get_D_3",
+    fun: curve_get_D_3_fun,
+    inputs: &[
+        balance_input("b0"),
+        balance_input("b1"),
+        balance_input("b2"),
+        FixedPointInput {
+            param: AMP,
+            bounds: FixedPointBounds { radix: 10, places: 0, min: 100.0, max: 1_000_000.0 },
+        },
+    ],
+    y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 300.0 },
+    num_points: FixedPointNumPoints { default: 100, min: 10, max: 2000 },
+    paths: GET_D_PATHS,
+};
+
+/// `curve_get_D` and the number of iterations it took to converge
+pub const curve_get_D_iterations: FixedPointFunctionMulti = FixedPointFunctionMulti {
+    name: "curve_get_D_iterations",
+    fun: curve_get_D_iterations_fun,
+    x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    outputs: &[
+        FixedPointOutput { name: "D", bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 } },
+        FixedPointOutput { name: "iterations", bounds: FixedPointBounds { radix: 10, places: 0, min: 0.0, max: 256.0 } },
+    ],
+    num_points: FixedPointNumPoints { default: 100, min: 10, max: 2000 },
+    params: POOL_PARAMS,
+};
+
+/// Amount of coin 1 out of the pool for `dx` of coin 0 in, after the fee, and the fee
+pub const curve_get_dy: FixedPointFunctionMulti = FixedPointFunctionMulti {
+    name: "curve_get_dy",
+    fun: curve_get_dy_fun,
+    x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    outputs: &[
+        FixedPointOutput { name: "dy", bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 } },
+        FixedPointOutput { name: "fee", bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 1.0 } },
+    ],
+    num_points: FixedPointNumPoints { default: 100, min: 10, max: 2000 },
+    params: &[AMP, BALANCE_0, FEE],
+};
+
+/// The pool's balances after `dx` of coin 0 is exchanged for coin 1, with the fee kept in the pool
+pub const curve_reserves_after_swap: FixedPointFunctionMulti = FixedPointFunctionMulti {
+    name: "curve_reserves_after_swap",
+    fun: curve_reserves_after_swap_fun,
+    x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    outputs: &[
+        FixedPointOutput { name: "balance 0", bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 101.0 } },
+        FixedPointOutput { name: "balance 1", bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 1.0 } },
+    ],
+    num_points: FixedPointNumPoints { default: 100, min: 10, max: 2000 },
+    params: &[AMP, BALANCE_0, FEE],
+};
 
-    let mut _xp = [ f64_to_u256(1.0, 10,18) ].to_vec();
-    _xp.push(x_n);
+/* Paths `get_D` can take, in the order it marks them */
+const GET_D_PATHS: &[&str] = &[
+    "early return: S == 0",
+    "converged, D > Dprev",
+    "converged, D <= Dprev",
+    "revert: did not converge",
+];
+/* `get_D`'s paths, then `curve_get_D_fun`'s own early return on the plotted balance */
+const CURVE_GET_D_PATHS: &[&str] = &[
+    "early return: S == 0",
+    "converged, D > Dprev",
+    "converged, D <= Dprev",
+    "revert: did not converge",
+    "early return: x == 0 or x >= 2^128 - 1",
+];
+
+const fn balance_input(name: &'static str) -> FixedPointInput {
+    FixedPointInput {
+        param: Parameter { name, default: "1000000000000000000", min: "1", max: "100000000000000000000" },
+        bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 100.0 },
+    }
+}
+
+/*
+ * Pool both solvers work on: two coins, where coin 0 holds the `balance0` parameter and the plotted
+ * input (or output, for `curve_get_y_D`) is the balance of coin 1. `amp` is A * POOL_A_PRECISION, at
+ * least A = 1 as the pool enforces
+ */
+pub const AMP: Parameter = Parameter { name: "amp", default: "30000", min: "100", max: "1000000" };
+pub const BALANCE_0: Parameter = Parameter {
+    name: "balance0",
+    default: "1000000000000000000",
+    min: "1",
+    max: "1000000000000000000000000",
+};
+/* Swap fee in units of FEE_DENOMINATOR, i.e. 4 bps by default */
+pub const FEE: Parameter = Parameter { name: "fee", default: "4000000", min: "0", max: "5000000000" };
+const POOL_PARAMS: &[Parameter] = &[AMP, BALANCE_0];
+const FEE_DENOMINATOR: u64 = 10_000_000_000;
+const POOL_A_PRECISION: u64 = 100;
+
+/* Extra decimal digits the reference invariant is solved to */
+const REF_DIGITS: u32 = 30;
+
+fn curve_get_D_fun(x_n: U256) -> U256 {
+    if x_n == u256d("0") || x_n >= u256d("340282366920938463463374607431768211455") { mark(4); return x_n; }
+    let _xp = [ params::get(&BALANCE_0), x_n ];
 
     // println!("{:?}", _xp);
 
+    get_D(&_xp, params::get(&AMP))
+}
+
+fn curve_get_D_balances_fun(x0: U256, x1: U256) -> U256 {
+    get_D(&[x0, x1], params::get(&AMP))
+}
+
+fn curve_get_D_3_fun(inputs: &[U256]) -> U256 {
+    get_D(&inputs[..3], inputs[3])
+}
+
+fn curve_get_D_iterations_fun(x_n: U256) -> Vec<U256> {
+    let (D, iterations) = get_D_iterations(&[ params::get(&BALANCE_0), x_n ], params::get(&AMP));
+    vec![D, u256f(iterations)]
+}
+
+/// Exchanges `dx` of coin 0 for coin 1 in a pool holding `balance0` of each, as the pool's `get_dy` does
+fn curve_get_dy_fun(dx: U256) -> Vec<U256> {
+    let (dy, fee) = get_dy(dx);
+    vec![dy, fee]
+}
+
+fn curve_reserves_after_swap_fun(dx: U256) -> Vec<U256> {
+    let balance = params::get(&BALANCE_0);
+    let (dy, _fee) = get_dy(dx);
+    vec![add(balance, dx), sub(balance, dy)]
+}
+
+/// The amount of coin 1 out for `dx` of coin 0 in, after the fee, and the fee
+fn get_dy(dx: U256) -> (U256, U256) {
+    let (amp, balance) = (params::get(&AMP), params::get(&BALANCE_0));
+    let xp = [ add(balance, dx), balance ];
+    let y = get_y_D(amp, 1, &xp, get_D(&[balance, balance], amp));
+    let dy = sub(sub(balance, y), u256f(1));
+    let fee = div(mul(params::get(&FEE), dy), u256f(FEE_DENOMINATOR));
+    (sub(dy, fee), fee)
+}
+
+/// The StableSwap invariant of a pool with balances `_xp`, as computed by the pool's `get_D`
+pub fn get_D(_xp: &[U256], _amp: U256) -> U256 {
+    get_D_iterations(_xp, _amp).0
+}
+
+/// `get_D` and the number of iterations of its loop it took to converge
+pub fn get_D_iterations(_xp: &[U256], _amp: U256) -> (U256, usize) {
+    let A_PRECISION = u256f(POOL_A_PRECISION);
+
     let N_COINS = U256::from(_xp.len());
     let mut S = u256d("0");
-    for &x in &_xp {
-        S += x;
+    for &x in _xp {
+        gas::charge_loop();
+        S = add(S, x);
     }
-    if S == u256d("0") { return S; }
+    if branch(S == u256d("0")) { mark(0); return (S, 0); }
 
     let mut D = S;
-    let Ann = _amp * N_COINS;
+    let Ann = mul(_amp, N_COINS);
 
-    for i in 0..256 {
+    for _i in 0..256 {
+        gas::charge_loop();
         let mut D_P = D;
-        for &x in &_xp {
-            D_P = D_P * D / x;
+        for &x in _xp {
+            gas::charge_loop();
+            D_P = div(mul(D_P, D), x);
         }
-        D_P /= U256::pow(N_COINS, N_COINS);
+        D_P = div(D_P, pow(N_COINS, N_COINS));
         let Dprev = D;
 
         // println!("D {:?}", D);
@@ -45,69 +234,182 @@ fn curve_get_D_fun(x_n: U256) -> U256 {
         // println!("denom  {:?}",
 
 
-        D = (
-            (unsafe_div(Ann * S, A_PRECISION) + D_P * N_COINS) * D
-            /
-            (
-                unsafe_div((Ann - A_PRECISION) * D, A_PRECISION) +
-                unsafe_add(N_COINS, u256d("1")) * D_P
+        D = div(
+            mul(add(unsafe_div(mul(Ann, S), A_PRECISION), mul(D_P, N_COINS)), D),
+            add(
+                unsafe_div(mul(sub(Ann, A_PRECISION), D), A_PRECISION),
+                mul(unsafe_add(N_COINS, u256d("1")), D_P)
             )
         );
 
-        if D > Dprev {
-            if D - Dprev <= u256d("1") {
+        if branch(D > Dprev) {
+            if branch(sub(D, Dprev) <= u256d("1")) {
                 // println!("D {:?}", D);
-                return D;
-            }
-        } else {
-            if Dprev - D <= u256d("1") {
-                // println!("D {:?}", D);
-                return D;
+                mark(1);
+                return (D, _i + 1);
             }
+        } else if branch(sub(Dprev, D) <= u256d("1")) {
+            // println!("D {:?}", D);
+            mark(2);
+            return (D, _i + 1);
         }
     }
 
+    mark(3);
     panic!("Did not converge");
 }
 
+/*
+ * Exact StableSwap invariant for the same pool as `curve_get_D_fun`, to REF_DIGITS extra decimal digits.
+ *
+ * `curve_get_D_fun` iterates towards the root of
+ *     g(D) = Ann*S/A_PRECISION - (Ann/A_PRECISION - 1)*D - D^(N+1) / (N^N * prod(x))
+ * which is decreasing and changes sign on [0, S], so the root is found by bisecting on D * 10^REF_DIGITS.
+ */
+fn curve_get_D_ref(x_n: U256) -> Rational {
+    if x_n == u256d("0") || x_n >= u256d("340282366920938463463374607431768211455") { return Rational::from_u256(x_n); }
+    let _amp = u256_to_bigint(params::get(&AMP));
+    let A_PRECISION = BigInt::from(POOL_A_PRECISION);
+
+    let _xp = [ u256_to_bigint(params::get(&BALANCE_0)), u256_to_bigint(x_n) ];
+    let N_COINS = _xp.len() as u32;
+    let S: BigInt = _xp.iter().sum();
+    let Ann = &_amp * N_COINS;
+    let nn_prod = BigInt::from(N_COINS).pow(N_COINS) * _xp.iter().product::<BigInt>();
+
+    let scale = BigInt::from(10).pow(REF_DIGITS);
+    let (scale_n, scale_n1) = (scale.pow(N_COINS), scale.pow(N_COINS + 1));
+
+    // g(d / scale) multiplied by the positive N^N * prod(x) * A_PRECISION * scale^(N+1), so it has the sign of g
+    let g = |d: &BigInt| -> BigInt {
+        &Ann * &S * &nn_prod * &scale_n1
+            - (&Ann - &A_PRECISION) * d * &nn_prod * &scale_n
+            - &A_PRECISION * d.pow(N_COINS + 1)
+    };
+
+    // Invariant: g(lo) > 0 >= g(hi)
+    let (mut lo, mut hi) = (BigInt::zero(), &S * &scale);
+    while &hi - &lo > BigInt::from(1) {
+        let mid: BigInt = (&lo + &hi) / 2;
+        if g(&mid) > BigInt::zero() { lo = mid } else { hi = mid }
+    }
+    Rational::new(hi, scale)
+}
+
 fn curve_get_y_D(D: U256) -> U256 {
-    let AMP = u256d("30000");
-    let A_PRECISION = u256d("100");
+    get_y_D(params::get(&AMP), 1, &[ params::get(&BALANCE_0), u256d("0") ], D)
+}
 
-    let mut xp = [ u256d("1000000000000000000"), u256d("10000"), u256d("100000000000000000000") ].to_vec();
-    let i = 0;
+/// The balance of coin `i` that gives the invariant `D` with the other balances in `xp`, as computed
+/// by the pool's `get_y_D`. `xp[i]` itself is ignored
+pub fn get_y_D(A: U256, i: usize, xp: &[U256], D: U256) -> U256 {
+    let A_PRECISION = u256f(POOL_A_PRECISION);
 
     let mut S_ = u256d("0");
-    let _x = u256d("0");
-    let mut y_prev = u256d("0");
     let mut c = D;
     let N_COINS = u256f(xp.len());
-    let Ann = AMP * N_COINS;
+    let Ann = mul(A, N_COINS);
 
-
-    for _i in 0..xp.len() {
-        let mut _x;
-        if _i != i {
-            _x = xp[i];
-        } else {
-            continue;
-        }
-        S_ += _x;
+    for (_i, &_x) in xp.iter().enumerate() {
+        gas::charge_loop();
+        if _i == i { continue; }
+        S_ = add(S_, _x);
+        c = div(mul(c, D), mul(_x, N_COINS));
     }
-    c = c * D * A_PRECISION / (Ann * N_COINS);
-    let b = S_ + D * A_PRECISION / Ann;
+    c = div(mul(mul(c, D), A_PRECISION), mul(Ann, N_COINS));
+    let b = add(S_, div(mul(D, A_PRECISION), Ann));
     let mut y = D;
 
     for _i in 0..256 {
-        y_prev = y;
-        y = (y * y + c) / (u256f(2) * y + b - D);
-        if y > y_prev {
-            if y - y_prev <= u256f(1) { return y; }
-        } else {
-            if y_prev - y <= u256f(1) { return y; }
+        gas::charge_loop();
+        let y_prev = y;
+        y = div(add(mul(y, y), c), sub(add(mul(u256f(2), y), b), D));
+        if branch(y > y_prev) {
+            if branch(sub(y, y_prev) <= u256f(1)) { mark(0); return y; }
+        } else if branch(sub(y_prev, y) <= u256f(1)) {
+            mark(1);
+            return y;
         }
     }
 
-    return u256d("100000000000000000"); // 1e17
+    mark(2);
+    panic!("Did not converge");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_get_D_ref() -> Result<(), Box<dyn std::error::Error>> {
+        // A balanced pool's invariant is the sum of its balances
+        assert_eq!(curve_get_D_ref(u256d("1000000000000000000")), Rational::from_u256(u256d("2000000000000000000")));
+
+        for x in ["1000000000000000", "500000000000000000", "42000000000000000000"] {
+            let d = curve_get_D_fun(u256d(x));
+            let err = (&Rational::from_u256(d) - &curve_get_D_ref(u256d(x))).abs();
+            assert!(err <= Rational::from_u256(u256d("2")), "x = {x}, D = {d}, error = {err:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_D_paths() -> Result<(), Box<dyn std::error::Error>> {
+        let taken = |f: &dyn Fn() -> U256| {
+            crate::coverage::reset();
+            f();
+            crate::coverage::path_taken()
+        };
+        let zero = u256d("0");
+        let path = taken(&|| curve_get_D_3_fun(&[zero, zero, zero, u256d("30000")])).ok_or("no path")?;
+        assert_eq!(GET_D_PATHS[path], "early return: S == 0");
+        let path = taken(&|| curve_get_D_fun(zero)).ok_or("no path")?;
+        assert_eq!(CURVE_GET_D_PATHS[path], "early return: x == 0 or x >= 2^128 - 1");
+        let path = taken(&|| curve_get_D_fun(u256d("1000000000000000000"))).ok_or("no path")?;
+        assert!(CURVE_GET_D_PATHS[path].starts_with("converged"));
+        // Every path `get_D` marks means the same thing in both lists
+        assert_eq!(GET_D_PATHS, &CURVE_GET_D_PATHS[..GET_D_PATHS.len()]);
+        Ok(())
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_curve_get_dy() -> Result<(), Box<dyn std::error::Error>> {
+        let dx = u256d("1000000000000000");
+        let outputs = curve_get_dy_fun(dx);
+        assert_eq!(outputs.len(), curve_get_dy.outputs.len());
+        let (dy, fee) = (outputs[0], outputs[1]);
+        // A small trade in a balanced pool is close to 1:1, less the 4 bps fee
+        assert!(dy < dx && dy + fee < dx);
+        assert_eq!(fee, (dy + fee) * u256d("4000000") / u256d("10000000000"));
+        assert!(dx - dy - fee < dx / u256d("100000"), "dx = {dx}, dy = {dy}, fee = {fee}");
+
+        let outputs = curve_get_D_iterations_fun(u256d("1000000000000000000"));
+        assert_eq!(outputs[0], u256d("2000000000000000000"));
+        assert!(outputs[1] >= u256d("1") && outputs[1] <= u256d("256"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_curve_reserves_after_swap() -> Result<(), Box<dyn std::error::Error>> {
+        let balance = u256d("1000000000000000000");
+        let D0 = get_D(&[balance, balance], u256d("30000"));
+        for dx in ["1000000000000000", "500000000000000000", "42000000000000000000"] {
+            let reserves = curve_reserves_after_swap_fun(u256d(dx));
+            // The fee stays in the pool, so its invariant doesn't decrease
+            let D1 = get_D(&reserves, u256d("30000"));
+            assert!(D1 >= D0, "dx = {dx}, D0 = {D0}, D1 = {D1}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_curve_get_y_D_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        for x in ["1000000000000000", "500000000000000000", "1000000000000000000", "42000000000000000000"] {
+            let x = u256d(x);
+            let y = curve_get_y_D(curve_get_D_fun(x));
+            let residual = if y > x { y - x } else { x - y };
+            assert!(residual <= u256d("2"), "x = {x}, get_y(get_D(x)) = {y}");
+        }
+        Ok(())
+    }
+}
@@ -1,24 +1,62 @@
 use primitive_types::{U256, U512};
 use crate::maths::*;
+use crate::gas::branch;
+use crate::coverage::mark;
+use crate::vyper;
+use crate::rational::Rational;
 
 pub fn x_mul_inverse_fun(x: U256) -> U256 {
-    if x > u256d("500000000000000000000000000000000000") {
+    if branch(x > u256d("500000000000000000000000000000000000")) {
+        mark(2);
         panic!("error");
     }
 
-    if x > u256f(0) {
+    if branch(x > u256f(0)) {
+        mark(0);
         mul(x, div(U256::from(10u128.pow(18)), x))
     } else {
+        mark(1);
+        u256f(0)
+    }
+}
+
+/*
+ * Same product as `x_mul_inverse_fun` computed as x * 1e18 / x in a single 512-bit step, so it rounds
+ * once instead of after each of the two fixed-point operations
+ */
+pub fn x_mul_inverse_full_fun(x: U256) -> U256 {
+    let wad = U256::from(10u128.pow(18));
+    if branch(x > u256d("500000000000000000000000000000000000")) {
+        mark(2);
+        panic!("error");
+    }
+
+    if branch(x > u256f(0)) {
+        mark(0);
+        full_mul_div(x, wad, x)
+    } else {
+        mark(1);
         u256f(0)
     }
 }
 
 fn mul(x: U256, y: U256) -> U256 {
-    x * y / U256::from(10u128.pow(18))
+    vyper::div(vyper::mul(x, y), U256::from(10u128.pow(18)))
 }
 
 fn div(x: U256, y: U256) -> U256 {
-    x * U256::from(10u128.pow(18))/ y
+    vyper::div(vyper::mul(x, U256::from(10u128.pow(18))), y)
+}
+
+/*
+ * Ideal value of x * (1/x) in raw units: exactly one, or zero when x is zero
+ */
+fn x_mul_inverse_ref(x: U256) -> Rational {
+    if x.is_zero() {
+        Rational::from_u256(U256::zero())
+    } else {
+        Rational::from_u256(U256::from(10u128.pow(18)))
+    }
 }
 
 /*
@@ -37,6 +75,26 @@ pub const x_mul_inverse: FixedPointFunction = FixedPointFunction {
     x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 1e18 },
     y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 1.0 },
     num_points: FixedPointNumPoints { default: 5000, min: 100, max: 10000 },
+    paths: &["x > 0", "x == 0", "revert: x > 5e35"],
+    reference: Some(x_mul_inverse_ref),
+    monotonicity: None,
+    rounding: Some(Rounding::Down),
+    invariants: &["y <= 1e18", "y == 0 implies x == 0"],
+    params: &[],
+};
+
+pub const x_mul_inverse_full: FixedPointFunction = FixedPointFunction {
+    name: "x_mul_inverse_full",
+    fun: x_mul_inverse_full_fun,
+    x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 1e18 },
+    y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 1.0 },
+    num_points: FixedPointNumPoints { default: 5000, min: 100, max: 10000 },
+    paths: &["x > 0", "x == 0", "revert: x > 5e35"],
+    reference: Some(x_mul_inverse_ref),
+    monotonicity: None,
+    rounding: Some(Rounding::Down),
+    invariants: &["y <= 1e18", "y == 0 implies x == 0"],
+    params: &[],
 };
 
 // /*
@@ -80,6 +138,7 @@ pub const x_mul_inverse: FixedPointFunction = FixedPointFunction {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sampling::{sample_curve_u256_safe, SampleOptions};
 
     #[test]
     fn test_full_math_div() -> Result<(), Box<dyn std::error::Error>> {
@@ -95,4 +154,15 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_x_mul_inverse_full_differs() -> Result<(), Box<dyn std::error::Error>> {
+        let options = SampleOptions { compare_with: Some(&x_mul_inverse_full), ..Default::default() };
+        let samples = sample_curve_u256_safe(&x_mul_inverse, &options, 1000, 0.0, 10.0);
+        let mismatches = samples.iter().filter(|s| s.other_y_raw.is_some_and(|other| other != s.y_raw)).count();
+        assert!(mismatches > 0);
+        // Rounding once never loses more than the two-step version
+        assert!(samples.iter().all(|s| s.other_y_raw.flatten() >= s.y_raw));
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 
 use primitive_types::{U256};
 use crate::vyper::*;
+use crate::gas::{self, branch};
+use crate::coverage::mark;
 use crate::maths::*;
+use crate::params::{self, Parameter};
 
 pub const yearn_calc_supply: FixedPointFunction = FixedPointFunction {
     name: "yearn_calc_supply",
@@ -9,6 +12,26 @@ pub const yearn_calc_supply: FixedPointFunction = FixedPointFunction {
     x_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 2.0  },
     y_bounds: FixedPointBounds { radix: 10, places: 18, min: 0.0, max: 10.0 },
     num_points: FixedPointNumPoints { default: 100, min: 5, max: 10000 },
+    paths: &["converged, sp >= s", "converged, sp < s", "revert: did not converge"],
+    reference: None,
+    monotonicity: Some(Monotonicity { direction: Direction::Increasing, strict: false }),
+    rounding: Some(Rounding::Down),
+    invariants: &[],
+    params: &[VB_SUM, SUPPLY],
+};
+
+/* Pool state the supply is solved against, defaulting to the snapshot after add_liquidity 1 below */
+const VB_SUM: Parameter = Parameter {
+    name: "vb_sum",
+    default: "5314420781261619946859",
+    min: "1000000000000000000",
+    max: "100000000000000000000000",
+};
+const SUPPLY: Parameter = Parameter {
+    name: "supply",
+    default: "2511236098261249777670",
+    min: "1000000000000000000",
+    max: "100000000000000000000000",
 };
 
 fn yearn_calc_supply_fun(vb_prod: U256) -> U256 {
@@ -19,14 +42,16 @@ fn yearn_calc_supply_fun(vb_prod: U256) -> U256 {
     let PRECISION = u256d("1000000000000000000");
 
     let AMP    = u256d("450000000000000000000");
-    let mut d  = u256d("449000000000000000000");
+    let d  = u256d("449000000000000000000");
 
     // let vb_sum: U256 = u256d("10901945277009618639966");
     // let mut s: U256  = u256d("2514337702656951993513");
 
     // add_liquidity 1
-    let vb_sum: U256 = u256d("5314420781261619946859");
-    let mut s: U256  = u256d("2511236098261249777670");
+    // let vb_sum: U256 = u256d("5314420781261619946859");
+    // let mut s: U256  = u256d("2511236098261249777670");
+    let vb_sum: U256 = params::get(&VB_SUM);
+    let mut s: U256  = params::get(&SUPPLY);
 
     // // add_liquidity 2
     // let vb_sum: U256 = u256d("10000027134684780493830");
@@ -36,28 +61,31 @@ fn yearn_calc_supply_fun(vb_prod: U256) -> U256 {
     // let vb_sum: U256 = u256d("10000043909621138586861");
     // let mut s: U256  = u256d("2512767327443788939269");
 
-    let mut l  = AMP * vb_sum;
+    let l  = mul(AMP, vb_sum);
     let mut r  = vb_prod;
 
     for _ in 0..=255 {
+        gas::charge_loop();
         // println!("l sp/loop: {l}");
         // println!("s sp/loop: {s}");
         // println!("r sp/loop: {r}");
         // println!("d sp/loop: {d}");
-        let mut sp = unsafe_div(unsafe_sub(l, unsafe_mul(s,r)), d);
+        let sp = unsafe_div(unsafe_sub(l, unsafe_mul(s,r)), d);
         // println!("sp sp/loop: {sp}");
-        for i in 0..8 {
+        for _ in 0..8 {
+            gas::charge_loop();
             // println!("r r/before {r}");
             r = unsafe_div(unsafe_mul(r, sp), s);
             // println!("r r/after {r}");
         }
-        let mut delta = if sp >= s { unsafe_sub(sp, s) } else { unsafe_sub(s, sp)};
-        if unsafe_div(unsafe_mul(delta, PRECISION), s) <= MAX_POW_REL_ERR {
+        let delta = if branch(sp >= s) { mark(0); unsafe_sub(sp, s) } else { mark(1); unsafe_sub(s, sp)};
+        if branch(unsafe_div(unsafe_mul(delta, PRECISION), s) <= MAX_POW_REL_ERR) {
             if debug { println!("vb_prod {vb_prod} , r {r}") }
             return r; // Just returning r unlike the Vyper function from the Yearn Stableswap pool
         }
         s = sp;
     }
+    mark(2);
     panic!("did not converge");
 }
 
@@ -0,0 +1,75 @@
+use std::cell::Cell;
+use primitive_types::{U256};
+
+/*
+ * Rough EVM gas accounting for the ported Vyper/Solidity functions.
+ *
+ * The arithmetic helpers in `vyper.rs` charge the cost of the opcode they stand in for, so a
+ * function written with them accumulates an estimate of what the original contract would burn.
+ * Costs come from the fee schedule in Appendix G of the Yellow Paper. Memory, stack shuffling and
+ * call overhead are ignored, so treat the figures as relative rather than exact.
+ */
+
+pub const G_VERYLOW: u64 = 3; // ADD, SUB, LT, GT, EQ, ISZERO
+pub const G_LOW: u64 = 5; // MUL, DIV, MOD
+pub const G_MID: u64 = 8; // JUMP
+pub const G_HIGH: u64 = 10; // JUMPI
+pub const G_EXP: u64 = 10;
+pub const G_EXPBYTE: u64 = 50; // per byte of the exponent
+
+/* Overflow check emitted by Vyper for checked arithmetic: a comparison and a conditional jump */
+pub const G_CHECK: u64 = G_VERYLOW + G_HIGH;
+
+/* One loop iteration: increment the counter, compare against the bound and jump back */
+pub const G_LOOP: u64 = G_VERYLOW + G_VERYLOW + G_HIGH + G_MID;
+
+thread_local! {
+    static GAS_USED: Cell<u64> = const { Cell::new(0) };
+}
+
+pub fn charge(gas: u64) {
+    GAS_USED.with(|g| g.set(g.get().saturating_add(gas)));
+}
+
+pub fn charge_exp(exponent: U256) {
+    let exponent_bytes = exponent.bits().div_ceil(8) as u64;
+    charge(G_EXP + G_EXPBYTE * exponent_bytes);
+}
+
+pub fn charge_loop() {
+    charge(G_LOOP);
+}
+
+/*
+ * Charges for the comparison and conditional jump of an `if` and passes the condition through,
+ * so instrumented code reads `if branch(a > b) { ... }`
+ */
+pub fn branch(cond: bool) -> bool {
+    charge(G_VERYLOW + G_HIGH);
+    cond
+}
+
+pub fn reset() {
+    GAS_USED.with(|g| g.set(0));
+}
+
+pub fn gas_used() -> u64 {
+    GAS_USED.with(|g| g.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge_exp() -> Result<(), Box<dyn std::error::Error>> {
+        reset();
+        charge_exp(U256::from(0));
+        assert_eq!(gas_used(), G_EXP);
+
+        reset();
+        charge_exp(U256::from(256));
+        assert_eq!(gas_used(), G_EXP + 2 * G_EXPBYTE);
+        Ok(())
+    }
+}
@@ -1,23 +1,143 @@
+// The ported Vyper/Solidity code keeps the identifiers of the original contracts
+#![allow(non_snake_case, non_upper_case_globals, clippy::needless_return)]
+
 use eframe::egui::{self, Color32, Slider};
-use egui_plot::{Plot, PlotPoints, Points};
-use std::panic::{self, AssertUnwindSafe};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use num_traits::ToPrimitive;
+use primitive_types::{U256};
+use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotImage, PlotPoint, PlotPoints, Points};
 
 mod maths;
 mod functions;
 mod vyper;
+mod gas;
+mod params;
+mod slicing;
+mod coverage;
+mod rational;
+mod reference;
+mod sampling;
+mod expr;
+mod search;
+mod analysis;
+mod scenario;
 
 
 use crate::functions::*;
 use crate::maths::*;
+use crate::reference::*;
+use crate::sampling::*;
+use crate::search::*;
+use crate::rational::Rational;
+use crate::analysis::boundary::*;
+use crate::analysis::plateau::{detect_plateaus, Plateau};
+use crate::analysis::anomaly::{detect_anomalies, Anomaly};
+use crate::analysis::derivative::{self, Step};
+use crate::analysis::distribution::{self, Histogram};
+use crate::analysis::stats::SampleStats;
+use crate::analysis::profit::{self, RoundTrip};
+use crate::analysis::iterate::{iterate, Orbit};
+use crate::analysis::param_search::{self, ParamCandidate, ParamObjective};
+use crate::scenario::curve_pool::{curve_pool, PoolState};
+use crate::scenario::Run;
+use crate::analysis::monotonic;
+use crate::analysis::invariant::*;
+use crate::analysis::metamorphic::Relation;
+use crate::analysis::rounding::{self, WrongRounding};
 
 /* Change this */
-const FIXED_POINT_FUN: FixedPointFunction = mul_by_inverse::x_mul_inverse;
+const FIXED_POINT_FUN: &FixedPointFunction = &mul_by_inverse::x_mul_inverse;
 
 const MARGIN: f64 = 0.03;
 
+/* Fraction of the central panel's height given to the main plot when subplots are shown below it */
+const MAIN_PLOT_FRACTION: f32 = 0.6;
+
+/* Colours assigned to code paths, in path ID order */
+const PATH_COLORS: [Color32; 6] = [
+    Color32::DARK_BLUE,
+    Color32::DARK_GREEN,
+    Color32::from_rgb(230, 140, 0),
+    Color32::from_rgb(140, 0, 200),
+    Color32::BROWN,
+    Color32::from_rgb(0, 160, 160),
+];
+
+/* Zoom factor applied to the current view when jumping to an input */
+const JUMP_ZOOM: f64 = 100.0;
+
+/* Most violations the monotonicity check bisects down to adjacent integers */
+const MAX_MINIMISED_VIOLATIONS: usize = 100;
+
+/* Most rows listed in the tools panel for any one analysis */
+const MAX_LISTED: usize = 100;
+
+/* Most function evaluations a parameter search may run, as it runs on the UI thread; far fewer when
+ * every evaluation also computes the function's reference */
+const MAX_PARAM_SEARCH_EVALUATIONS: usize = 200_000;
+const MAX_PARAM_SEARCH_REFERENCE_EVALUATIONS: usize = 5_000;
+
+/* Height of each histogram in the distribution panel */
+const HISTOGRAM_HEIGHT: f32 = 180.0;
+
+/* Height of the iteration panel's sequence and cobweb plots */
+const ITERATION_PLOT_HEIGHT: f32 = 180.0;
+
+/* Points the function's curve is sampled at for the cobweb diagram */
+const COBWEB_CURVE_POINTS: usize = 200;
+
+/* Colours of the lowest to the highest results in a heatmap; reverted cells are red */
+const HEATMAP_COLORS: [Color32; 3] = [
+    Color32::from_rgb(30, 30, 120),
+    Color32::from_rgb(30, 160, 130),
+    Color32::from_rgb(250, 230, 40),
+];
+
+/// A sampled two-input function and the texture its cells are drawn with
+struct Heatmap {
+    grid: Grid,
+    texture: egui::TextureHandle,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SearchObjective {
+    Reference,
+    Difference,
+    Expression,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RelationKind {
+    None,
+    Scaling,
+    Increment,
+    Inverse,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParamObjectiveKind {
+    MaxError,
+    Reverts,
+    NonMonotonic,
+    Profit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    Wei,
+    Spacing,
+    Custom,
+}
+
 pub struct EllipticApp {
+    fun: &'static FixedPointFunction,
+    // The function of several inputs `fun` is a slice of, and the input plotted along x
+    slice: Option<(&'static FixedPointFunctionN, usize)>,
+    // Second function to plot the difference against
+    other_fun: Option<&'static FixedPointFunction>,
+    num_mismatches: usize,
+
     // Sampling bounds (limits on what values can be sampled)
     sampling_x_min: f64,
     sampling_x_max: f64,
@@ -28,57 +148,1576 @@ pub struct EllipticApp {
     display_y_min: f64,
     display_y_max: f64,
 
-    // Input fields
-    sampling_x_min_input: String,
-    sampling_x_max_input: String,
-    display_x_min_input: String,
-    display_x_max_input: String,
-    display_y_min_input: String,
-    display_y_max_input: String,
+    // Input fields
+    sampling_x_min_input: String,
+    sampling_x_max_input: String,
+    display_x_min_input: String,
+    display_x_max_input: String,
+    display_y_min_input: String,
+    display_y_max_input: String,
+
+    current_bounds: Option<egui_plot::PlotBounds>,
+    reset_view: bool,
+    num_points: usize,
+    show_gas: bool,
+    show_derivative: bool,
+    show_distributions: bool,
+    histogram_bins: usize,
+    residue_digits: u32,
+    derivative_step: StepKind,
+    derivative_custom_step: String,
+    color_by_path: bool,
+    paths_not_hit: Vec<usize>,
+    show_reference: bool,
+    error_unit: ErrorUnit,
+    bits_measure: Option<BitsMeasure>,
+    worst_bits: Option<(U256, f64)>,
+    // Samples from the last frame, for the analysis tools
+    samples: Vec<Sample>,
+    revert_boundaries: Vec<RevertBoundary>,
+    plateaus: Vec<Plateau>,
+    stats: Option<SampleStats>,
+    show_anomalies: bool,
+    anomaly_threshold: f64,
+    anomalies: Vec<Anomaly>,
+
+    // Monotonicity check
+    expected_monotonicity: Option<Monotonicity>,
+    neighbourhood_radius: u64,
+    monotonicity_violations: Vec<monotonic::Violation>,
+    minimal_violations: Vec<monotonic::Violation>,
+
+    // Invariants checked on every sample
+    invariants: Vec<Invariant>,
+    new_invariant: String,
+    invariant_violations: Vec<InvariantViolation>,
+    invariant_errors: Vec<usize>,
+
+    // Rounding direction
+    rounding: Option<Rounding>,
+    // Whether to compute the reference every frame to check the rounding against it
+    check_rounding: bool,
+    wrong_rounding: Vec<WrongRounding>,
+
+    // Round-trip profit
+    profit_inverse: Option<&'static FixedPointFunction>,
+    profit_expression: String,
+    show_profit: bool,
+    profit_results: Vec<Candidate>,
+    profit_error: Option<String>,
+
+    // Iteration
+    iterate_start: String,
+    iterate_steps: usize,
+    orbit: Option<Orbit>,
+    cobweb_curve: Vec<[f64; 2]>,
+    iterate_error: Option<String>,
+
+    // Parameters
+    param_inputs: std::collections::BTreeMap<&'static str, String>,
+    param_objective: ParamObjectiveKind,
+    param_search_random: bool,
+    param_per_axis: usize,
+    param_count: usize,
+    param_points: usize,
+    param_results: Vec<ParamCandidate>,
+    param_search_error: Option<String>,
+
+    // Heatmap of a two-input function
+    show_heatmap: bool,
+    heatmap_fun: &'static FixedPointFunction2,
+    heatmap_cells: usize,
+    heatmap_x_range: (f64, f64),
+    heatmap_y_range: (f64, f64),
+    heatmap: Option<Heatmap>,
+
+    // Function with several outputs
+    show_outputs: bool,
+    outputs_fun: &'static FixedPointFunctionMulti,
+    outputs_visible: Vec<bool>,
+    outputs_num_points: usize,
+    outputs_x_range: (f64, f64),
+    // Outputs plotted against each other as the input sweeps its range, instead of against the input
+    outputs_parametric: Option<(usize, usize)>,
+
+    // Scenario
+    show_scenario: bool,
+    scenario_steps: Vec<(usize, String)>,
+    scenario_varied: usize,
+    scenario_variable: usize,
+    scenario_min: String,
+    scenario_max: String,
+    scenario_points: usize,
+    scenario_amounts: Vec<U256>,
+    scenario_runs: Vec<Run<PoolState>>,
+    scenario_error: Option<String>,
+
+    // Metamorphic relation
+    relation_kind: RelationKind,
+    relation_k: String,
+    relation_tolerance: String,
+    relation_min: String,
+    relation_max: String,
+    relation_inverse: Option<&'static FixedPointFunction>,
+    show_round_trip: bool,
+    num_relation_violations: usize,
+
+    // Max-error search
+    search_objective: SearchObjective,
+    search_expression: String,
+    search_budget: usize,
+    search_top_k: usize,
+    search_runs: u64,
+    search_results: Vec<Candidate>,
+    search_error: Option<String>,
+
+    error_message: Option<String>,
+    last_error_x: Option<f64>,
+    fps: f32,
+    frame_times: Vec<f64>,
+    last_frame_time: std::time::Instant,
+}
+
+impl Default for EllipticApp {
+    fn default() -> Self {
+        /* These bounds must be pre-divided by radix^places */
+        let (xb, yb) = (&FIXED_POINT_FUN.x_bounds, &FIXED_POINT_FUN.y_bounds);
+        let (x_min, x_max, y_min, y_max) = (xb.min, xb.max, yb.min, yb.max);
+        Self {
+            fun: FIXED_POINT_FUN,
+            slice: None,
+            other_fun: None,
+            num_mismatches: 0,
+
+            // Initialize sampling bounds
+            sampling_x_min: x_min,
+            sampling_x_max: x_max,
+
+            // Initialize display bounds
+            display_x_min: x_min,
+            display_x_max: x_max,
+            display_y_min: y_min,
+            display_y_max: y_max,
+
+            // Initialize input fields
+            sampling_x_min_input: x_min.to_string(),
+            sampling_x_max_input: x_max.to_string(),
+            display_x_min_input: x_min.to_string(),
+            display_x_max_input: x_max.to_string(),
+            display_y_min_input: y_min.to_string(),
+            display_y_max_input: y_max.to_string(),
+
+            current_bounds: None,
+            reset_view: false,
+            num_points: FIXED_POINT_FUN.num_points.default,
+            show_gas: false,
+            show_derivative: false,
+            show_distributions: false,
+            histogram_bins: 20,
+            residue_digits: 1,
+            derivative_step: StepKind::Wei,
+            derivative_custom_step: "1000".to_string(),
+            color_by_path: false,
+            paths_not_hit: Vec::new(),
+            show_reference: false,
+            error_unit: ErrorUnit::Ulp,
+            bits_measure: None,
+            worst_bits: None,
+            samples: Vec::new(),
+            revert_boundaries: Vec::new(),
+            plateaus: Vec::new(),
+            stats: None,
+            show_anomalies: true,
+            anomaly_threshold: 20.0,
+            anomalies: Vec::new(),
+            expected_monotonicity: FIXED_POINT_FUN.monotonicity,
+            neighbourhood_radius: 4,
+            monotonicity_violations: Vec::new(),
+            minimal_violations: Vec::new(),
+            invariants: FIXED_POINT_FUN.invariants.iter().map(|src| Invariant::new(src)).collect(),
+            new_invariant: String::new(),
+            invariant_violations: Vec::new(),
+            invariant_errors: Vec::new(),
+            rounding: FIXED_POINT_FUN.rounding,
+            check_rounding: false,
+            wrong_rounding: Vec::new(),
+            profit_inverse: None,
+            profit_expression: "z - x".to_string(),
+            show_profit: false,
+            profit_results: Vec::new(),
+            profit_error: None,
+            iterate_start: "3".to_string(),
+            iterate_steps: 100,
+            orbit: None,
+            cobweb_curve: Vec::new(),
+            iterate_error: None,
+            param_inputs: std::collections::BTreeMap::new(),
+            param_objective: ParamObjectiveKind::Reverts,
+            param_search_random: false,
+            param_per_axis: 5,
+            param_count: 100,
+            param_points: 50,
+            param_results: Vec::new(),
+            param_search_error: None,
+            show_heatmap: false,
+            heatmap_fun: ALL_2[0],
+            heatmap_cells: ALL_2[0].num_points.default,
+            heatmap_x_range: (ALL_2[0].x_bounds.min, ALL_2[0].x_bounds.max),
+            heatmap_y_range: (ALL_2[0].y_bounds.min, ALL_2[0].y_bounds.max),
+            heatmap: None,
+            show_outputs: false,
+            outputs_fun: ALL_MULTI[0],
+            outputs_visible: vec![true; ALL_MULTI[0].outputs.len()],
+            outputs_num_points: ALL_MULTI[0].num_points.default,
+            outputs_x_range: (ALL_MULTI[0].x_bounds.min, ALL_MULTI[0].x_bounds.max),
+            outputs_parametric: None,
+            show_scenario: false,
+            scenario_steps: vec![(0, "0".to_string()), (5, "1e18".to_string())],
+            scenario_varied: 0,
+            scenario_variable: 4,
+            scenario_min: "1e18".to_string(),
+            scenario_max: "1e20".to_string(),
+            scenario_points: 100,
+            scenario_amounts: Vec::new(),
+            scenario_runs: Vec::new(),
+            scenario_error: None,
+            relation_kind: RelationKind::None,
+            relation_k: "2".to_string(),
+            relation_tolerance: "1".to_string(),
+            relation_min: "0".to_string(),
+            relation_max: "1e18".to_string(),
+            relation_inverse: None,
+            show_round_trip: false,
+            num_relation_violations: 0,
+            search_objective: SearchObjective::Reference,
+            search_expression: "abs(y - ref)".to_string(),
+            search_budget: 5000,
+            search_top_k: 10,
+            search_runs: 0,
+            search_results: Vec::new(),
+            search_error: None,
+            error_message: None,
+            last_error_x: None,
+            fps: 0.0,
+            frame_times: Vec::with_capacity(100),
+            last_frame_time: std::time::Instant::now(),
+        }
+    }
+}
+
+impl EllipticApp {
+    /// Switches the plotted function, resetting the bounds and anything else that depends on it
+    fn select_function(&mut self, fun: &'static FixedPointFunction) {
+        let (xb, yb) = (&fun.x_bounds, &fun.y_bounds);
+        self.fun = fun;
+        self.other_fun = self.other_fun.filter(|other| other.name != fun.name && fun.is_comparable_with(other));
+
+        self.sampling_x_min = xb.min;
+        self.sampling_x_max = xb.max;
+        self.display_x_min = xb.min;
+        self.display_x_max = xb.max;
+        self.display_y_min = yb.min;
+        self.display_y_max = yb.max;
+
+        self.sampling_x_min_input = xb.min.to_string();
+        self.sampling_x_max_input = xb.max.to_string();
+        self.display_x_min_input = xb.min.to_string();
+        self.display_x_max_input = xb.max.to_string();
+        self.display_y_min_input = yb.min.to_string();
+        self.display_y_max_input = yb.max.to_string();
+
+        self.current_bounds = None;
+        self.reset_view = true;
+        self.num_points = fun.num_points.default;
+        self.paths_not_hit.clear();
+        self.search_results.clear();
+        self.revert_boundaries.clear();
+        self.plateaus.clear();
+        self.expected_monotonicity = fun.monotonicity;
+        self.monotonicity_violations.clear();
+        self.minimal_violations.clear();
+        self.invariants = fun.invariants.iter().map(|src| Invariant::new(src)).collect();
+        self.invariant_violations.clear();
+        self.invariant_errors.clear();
+        self.rounding = fun.rounding;
+        self.wrong_rounding.clear();
+        self.relation_inverse = self.relation_inverse.filter(|inverse| fun.is_invertible_by(inverse));
+        self.profit_inverse = self.profit_inverse.filter(|inverse| fun.is_invertible_by(inverse));
+        self.profit_results.clear();
+        self.orbit = None;
+        self.param_results.clear();
+        self.cobweb_curve.clear();
+    }
+
+    /// Plots `fun` along its input `axis`. Switching the axis of the function already plotted
+    /// keeps the settings that don't depend on the input
+    fn select_slice(&mut self, fun: &'static FixedPointFunctionN, axis: usize) {
+        let same_function = self.slice.is_some_and(|(current, _)| current.name == fun.name);
+        let (num_points, monotonicity, rounding) = (self.num_points, self.expected_monotonicity, self.rounding);
+        let invariants: Vec<String> = self.invariants.iter().map(|i| i.source.clone()).collect();
+        self.slice = Some((fun, axis));
+        self.select_function(slicing::slice(fun, axis));
+        if same_function {
+            self.num_points = num_points;
+            self.expected_monotonicity = monotonicity;
+            self.rounding = rounding;
+            self.invariants = invariants.iter().map(|src| Invariant::new(src)).collect();
+        }
+    }
+
+    fn slice_row(&mut self, ui: &mut egui::Ui, fun: &'static FixedPointFunctionN, axis: usize) {
+        ui.horizontal(|ui| {
+            ui.label("x axis:");
+            for (i, input) in fun.inputs.iter().enumerate() {
+                if ui.radio(i == axis, input.param.name).clicked() && i != axis {
+                    self.select_slice(fun, i);
+                }
+            }
+            ui.separator();
+            for input in fun.inputs.iter().filter(|input| input.param.name != fun.inputs[axis].param.name) {
+                let b = &input.bounds;
+                let mut value = u256_to_f64(params::get(&input.param), b.radix, b.places);
+                ui.label(input.param.name);
+                if ui.add(Slider::new(&mut value, b.min..=b.max).logarithmic(true)).changed() {
+                    let raw = f64_to_u256(value, b.radix, b.places);
+                    params::set(input.param.name, raw);
+                    self.param_inputs.insert(input.param.name, raw.to_string());
+                }
+            }
+        });
+    }
+
+    /// Centres the view on the raw input `x`, zoomed in from the current view
+    fn jump_to(&mut self, x: U256) {
+        let xb = &self.fun.x_bounds;
+        let x = u256_to_f64(x, xb.radix, xb.places);
+        let width = match self.current_bounds {
+            Some(bounds) => bounds.width(),
+            None => self.display_x_max - self.display_x_min,
+        } / JUMP_ZOOM;
+        self.display_x_min = x - width / 2.0;
+        self.display_x_max = x + width / 2.0;
+        self.display_x_min_input = self.display_x_min.to_string();
+        self.display_x_max_input = self.display_x_max.to_string();
+        self.reset_view = true;
+    }
+
+    /// Raw input range the sampling bounds allow
+    fn sampling_range_raw(&self) -> (U256, U256) {
+        let xb = &self.fun.x_bounds;
+        (f64_to_u256(self.sampling_x_min, xb.radix, xb.places), f64_to_u256(self.sampling_x_max, xb.radix, xb.places))
+    }
+
+    fn run_search(&mut self) {
+        let objective = match self.search_objective {
+            SearchObjective::Reference if self.fun.reference.is_some() => Objective::Reference(self.error_unit),
+            SearchObjective::Reference => {
+                self.search_error = Some("function has no reference".to_string());
+                return;
+            }
+            SearchObjective::Difference => match self.other_fun {
+                Some(other) => Objective::Difference(other),
+                None => {
+                    self.search_error = Some("select a function to compare with".to_string());
+                    return;
+                }
+            },
+            SearchObjective::Expression => match expr::parse(&self.search_expression) {
+                Ok(e) => Objective::Expression(e, self.other_fun),
+                Err(err) => {
+                    self.search_error = Some(err);
+                    return;
+                }
+            },
+        };
+        let (lo, hi) = self.sampling_range_raw();
+        self.search_runs += 1;
+        let config = SearchConfig { budget: self.search_budget, top_k: self.search_top_k, seed: self.search_runs };
+        let starts: Vec<U256> = self.samples.iter().map(|s| s.x_raw).collect();
+        self.search_results = search(self.fun, &objective, lo, hi, &starts, &config);
+        self.search_error = None;
+    }
+
+    /// The round trip configured in the profit panel, if its inverse is chosen
+    fn round_trip(&self) -> Result<Option<RoundTrip>, String> {
+        let Some(inverse) = self.profit_inverse else { return Ok(None) };
+        Ok(Some(RoundTrip { inverse, profit: expr::parse(&self.profit_expression)? }))
+    }
+
+    fn run_profit_search(&mut self) {
+        let round_trip = match self.round_trip() {
+            Ok(Some(round_trip)) => round_trip,
+            Ok(None) => {
+                self.profit_error = Some("select an inverse function".to_string());
+                return;
+            }
+            Err(err) => {
+                self.profit_error = Some(err);
+                return;
+            }
+        };
+        let (lo, hi) = self.sampling_range_raw();
+        self.search_runs += 1;
+        let config = SearchConfig { budget: self.search_budget, top_k: self.search_top_k, seed: self.search_runs };
+        let starts: Vec<U256> = self.samples.iter().map(|s| s.x_raw).collect();
+        self.profit_results = search(self.fun, &Objective::RoundTrip(round_trip), lo, hi, &starts, &config);
+        self.profit_error = None;
+    }
+
+    fn run_iteration(&mut self) {
+        let start = match expr::parse_constant(&self.iterate_start) {
+            Ok(start) => start,
+            Err(err) => {
+                self.iterate_error = Some(err);
+                return;
+            }
+        };
+        let Some(start) = rational::bigint_to_u256(&start) else {
+            self.iterate_error = Some("start must fit in a U256".to_string());
+            return;
+        };
+        let orbit = iterate(self.fun, start, self.iterate_steps);
+
+        // The function's curve over the range the sequence visits, for the cobweb diagram
+        let xb = &self.fun.x_bounds;
+        let visited = orbit.values.iter().map(|&x| u256_to_f64(x, xb.radix, xb.places));
+        let (lo, hi) = visited.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        self.cobweb_curve = if lo < hi {
+            sample_curve_u256_safe(self.fun, &SampleOptions::default(), COBWEB_CURVE_POINTS, lo, hi).iter()
+                .filter_map(|s| s.y.map(|y| [s.x, y]))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.orbit = Some(orbit);
+        self.iterate_error = None;
+    }
+
+    fn iteration_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Iteration").default_open(false).show(ui, |ui| {
+            if !self.fun.is_iterable() {
+                ui.label("The output's format differs from the input's");
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label("x₀ (raw):");
+                ui.text_edit_singleline(&mut self.iterate_start);
+            });
+            ui.add(Slider::new(&mut self.iterate_steps, 1..=10_000).logarithmic(true).text("steps"));
+            if ui.button("Iterate x ← f(x)").clicked() {
+                self.run_iteration();
+            }
+            if let Some(err) = &self.iterate_error {
+                ui.colored_label(Color32::RED, err);
+            }
+            let Some(orbit) = &self.orbit else { return };
+
+            let (first, last) = (orbit.values[0], *orbit.values.last().unwrap());
+            let drift = rational::u256_to_bigint(last) - rational::u256_to_bigint(first);
+            ui.label(format!("x_{} = {last}, drift {drift} raw", orbit.values.len() - 1));
+            if let Some(step) = orbit.fixed_point_step() {
+                ui.label(format!("Fixed point from step {step}"));
+            }
+            if let Some(message) = &orbit.revert {
+                ui.colored_label(Color32::RED, format!("Reverted at the last value: {message}"));
+            }
+
+            let xb = &self.fun.x_bounds;
+            let values: Vec<f64> = orbit.values.iter().map(|&x| u256_to_f64(x, xb.radix, xb.places)).collect();
+            let sequence: Vec<[f64; 2]> = values.iter().enumerate().map(|(n, &x)| [n as f64, x]).collect();
+            let sequence_plot = Plot::new("iteration_sequence").height(ITERATION_PLOT_HEIGHT).x_axis_label("n").y_axis_label("xₙ");
+            sequence_plot.show(ui, |plot_ui| {
+                plot_ui.line(Line::new("xₙ", PlotPoints::new(sequence.clone())).color(Color32::DARK_BLUE));
+                plot_ui.points(Points::new("xₙ", PlotPoints::new(sequence)).color(Color32::DARK_BLUE).radius(2.0));
+            });
+
+            // Vertical to the curve, then horizontal to y = x, for each step
+            let mut cobweb = vec![[values[0], values[0]]];
+            for w in values.windows(2) {
+                cobweb.push([w[0], w[1]]);
+                cobweb.push([w[1], w[1]]);
+            }
+            let (lo, hi) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+            let cobweb_plot = Plot::new("cobweb").height(ITERATION_PLOT_HEIGHT).data_aspect(1.0).x_axis_label("x").y_axis_label("f(x)");
+            cobweb_plot.show(ui, |plot_ui| {
+                plot_ui.line(Line::new("y = x", PlotPoints::new(vec![[lo, lo], [hi, hi]])).color(Color32::GRAY));
+                plot_ui.line(Line::new("f(x)", PlotPoints::new(self.cobweb_curve.clone())).color(Color32::DARK_BLUE));
+                plot_ui.line(Line::new("cobweb", PlotPoints::new(cobweb)).color(Color32::RED));
+            });
+        });
+    }
+
+    /// Sets the parameters and the text shown for them
+    fn load_params(&mut self, values: &[U256]) {
+        for (p, &value) in self.fun.params.iter().zip(values) {
+            params::set(p.name, value);
+            self.param_inputs.insert(p.name, value.to_string());
+        }
+    }
+
+    fn params_row(&mut self, ui: &mut egui::Ui, params: &'static [params::Parameter]) {
+        ui.horizontal(|ui| {
+            ui.label("Parameters:");
+            for p in params {
+                ui.label(p.name);
+                let input = self.param_inputs.entry(p.name).or_insert_with(|| params::get(p).to_string());
+                let edit = ui.add(egui::TextEdit::singleline(input).desired_width(200.0))
+                    .on_hover_text(format!("raw, default {}", p.default));
+                match expr::parse_constant(input).map(|v| rational::bigint_to_u256(&v)) {
+                    Ok(Some(value)) => if edit.changed() { params::set(p.name, value) },
+                    Ok(None) => { ui.colored_label(Color32::RED, "doesn't fit in a U256"); }
+                    Err(err) => { ui.colored_label(Color32::RED, err); }
+                }
+            }
+            if ui.button("Defaults").clicked() {
+                for p in params {
+                    params::clear(p.name);
+                    self.param_inputs.remove(p.name);
+                }
+            }
+        });
+    }
+
+    fn param_combinations(&self) -> usize {
+        if self.param_search_random {
+            self.param_count
+        } else {
+            self.param_per_axis.saturating_pow(self.fun.params.len() as u32)
+        }
+    }
+
+    /// Evaluations of the function the parameter search would run
+    fn param_search_evaluations(&self) -> usize {
+        self.param_combinations().saturating_mul(self.param_points)
+    }
+
+    fn max_param_search_evaluations(&self) -> usize {
+        match self.param_objective {
+            ParamObjectiveKind::MaxError => MAX_PARAM_SEARCH_REFERENCE_EVALUATIONS,
+            _ => MAX_PARAM_SEARCH_EVALUATIONS,
+        }
+    }
+
+    fn run_param_search(&mut self) {
+        if self.param_search_evaluations() > self.max_param_search_evaluations() {
+            self.param_search_error = Some("too many evaluations, lower the combinations or samples".to_string());
+            return;
+        }
+        let objective = match self.param_objective {
+            ParamObjectiveKind::MaxError if self.fun.reference.is_some() => ParamObjective::MaxError(self.error_unit),
+            ParamObjectiveKind::NonMonotonic if let Some(m) = self.expected_monotonicity => ParamObjective::NonMonotonic(m),
+            ParamObjectiveKind::Reverts => ParamObjective::Reverts,
+            ParamObjectiveKind::Profit => match self.round_trip() {
+                Ok(Some(round_trip)) => ParamObjective::Profit(round_trip),
+                Ok(None) => {
+                    self.param_search_error = Some("select an inverse function in the profit panel".to_string());
+                    return;
+                }
+                Err(err) => {
+                    self.param_search_error = Some(err);
+                    return;
+                }
+            },
+            _ => {
+                self.param_search_error = Some("the objective needs a reference or an expected monotonicity".to_string());
+                return;
+            }
+        };
+        let (Some(first), Some(last)) = (self.samples.first(), self.samples.last()) else { return };
+        let candidates = if self.param_search_random {
+            self.search_runs += 1;
+            param_search::random(self.fun.params, self.param_count, &mut Rng(self.search_runs))
+        } else {
+            param_search::grid(self.fun.params, self.param_per_axis)
+        };
+        self.param_results = param_search::rank(self.fun, objective, candidates, first.x, last.x, self.param_points, MAX_LISTED);
+        self.param_search_error = None;
+    }
+
+    fn param_search_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Parameter search").default_open(false).show(ui, |ui| {
+            if self.fun.params.is_empty() {
+                ui.label("The function has no parameters");
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(self.fun.reference.is_some(), |ui| {
+                    ui.radio_value(&mut self.param_objective, ParamObjectiveKind::MaxError, "max error")
+                        .on_hover_text(format!("|error| in {} units", self.error_unit.label()));
+                });
+                ui.radio_value(&mut self.param_objective, ParamObjectiveKind::Reverts, "reverts");
+                ui.add_enabled_ui(self.expected_monotonicity.is_some(), |ui| {
+                    ui.radio_value(&mut self.param_objective, ParamObjectiveKind::NonMonotonic, "non-monotonic pairs");
+                });
+                ui.add_enabled_ui(self.profit_inverse.is_some(), |ui| {
+                    ui.radio_value(&mut self.param_objective, ParamObjectiveKind::Profit, "round-trip profit")
+                        .on_hover_text("Round trip through the inverse and profit set in the profit panel");
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.param_search_random, false, "grid");
+                ui.radio_value(&mut self.param_search_random, true, "random");
+            });
+            if self.param_search_random {
+                ui.add(Slider::new(&mut self.param_count, 10..=10_000).logarithmic(true).text("combinations"));
+            } else {
+                let combinations = self.param_combinations();
+                ui.add(Slider::new(&mut self.param_per_axis, 2..=50).text(format!("values per parameter ({combinations} combinations)")));
+            }
+            ui.add(Slider::new(&mut self.param_points, 5..=1000).logarithmic(true).text("samples per combination"));
+            let (evaluations, max) = (self.param_search_evaluations(), self.max_param_search_evaluations());
+            ui.horizontal(|ui| {
+                if ui.add_enabled(evaluations <= max, egui::Button::new("Search the view")).clicked() {
+                    self.run_param_search();
+                }
+                let cost = format!("{evaluations} evaluations");
+                if evaluations <= max {
+                    ui.label(cost);
+                } else {
+                    ui.colored_label(Color32::RED, format!("{cost}, more than the {max} allowed"));
+                }
+            });
+            if let Some(err) = &self.param_search_error {
+                ui.colored_label(Color32::RED, err);
+            }
+
+            let mut load = None;
+            egui::Grid::new("param_results").striped(true).show(ui, |ui| {
+                if !self.param_results.is_empty() {
+                    for p in self.fun.params {
+                        ui.strong(p.name);
+                    }
+                    ui.strong("score");
+                    ui.end_row();
+                }
+                for candidate in &self.param_results {
+                    for value in &candidate.values {
+                        ui.label(value.to_string());
+                    }
+                    if ui.link(format!("{:.6e}", candidate.score)).on_hover_text("Load into the plot").clicked() {
+                        load = Some(candidate.values.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(values) = load {
+                self.load_params(&values);
+            }
+        });
+    }
+
+    fn select_heatmap_function(&mut self, fun: &'static FixedPointFunction2) {
+        self.heatmap_fun = fun;
+        self.heatmap_cells = fun.num_points.default;
+        self.heatmap_x_range = (fun.x_bounds.min, fun.x_bounds.max);
+        self.heatmap_y_range = (fun.y_bounds.min, fun.y_bounds.max);
+        self.heatmap = None;
+    }
+
+    fn run_heatmap(&mut self, ctx: &egui::Context) {
+        let fun = self.heatmap_fun;
+        let n = self.heatmap_cells;
+        let grid = sample_grid(fun, (n, n), self.heatmap_x_range, self.heatmap_y_range);
+        let zb = &fun.z_bounds;
+        let (lo, hi) = grid.range()
+            .map(|(lo, hi)| (u256_to_f64(lo, zb.radix, zb.places), u256_to_f64(hi, zb.radix, zb.places)))
+            .unwrap_or((0.0, 0.0));
+        // Image rows run from the top, grid rows from the lowest second input
+        let pixels: Vec<Color32> = (0..n).rev()
+            .flat_map(|row| (0..n).map(move |column| (column, row)))
+            .map(|(column, row)| match grid.get(column, row) {
+                Ok(z) => heat_color(if hi > lo { (u256_to_f64(*z, zb.radix, zb.places) - lo) / (hi - lo) } else { 0.0 }),
+                Err(_) => Color32::RED,
+            })
+            .collect();
+        let image = egui::ColorImage::new([n, n], pixels);
+        let texture = ctx.load_texture("heatmap", image, egui::TextureOptions::NEAREST);
+        self.heatmap = Some(Heatmap { grid, texture, x_range: self.heatmap_x_range, y_range: self.heatmap_y_range });
+    }
+
+    fn heatmap_window(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut selected = None;
+            egui::ComboBox::from_id_salt("heatmap_function")
+                .selected_text(self.heatmap_fun.name)
+                .show_ui(ui, |ui| {
+                    for &fun in ALL_2 {
+                        if ui.selectable_label(fun.name == self.heatmap_fun.name, fun.name).clicked() {
+                            selected = Some(fun);
+                        }
+                    }
+                });
+            if let Some(fun) = selected {
+                self.select_heatmap_function(fun);
+            }
+            let np = &self.heatmap_fun.num_points;
+            ui.add(Slider::new(&mut self.heatmap_cells, np.min..=np.max).logarithmic(true).text("cells per axis"));
+        });
+        if !self.heatmap_fun.params.is_empty() {
+            self.params_row(ui, self.heatmap_fun.params);
+        }
+        ui.horizontal(|ui| {
+            let (xb, yb) = (&self.heatmap_fun.x_bounds, &self.heatmap_fun.y_bounds);
+            ui.label("x:");
+            ui.add(egui::DragValue::new(&mut self.heatmap_x_range.0).range(xb.min..=xb.max).speed(0.1));
+            ui.add(egui::DragValue::new(&mut self.heatmap_x_range.1).range(xb.min..=xb.max).speed(0.1));
+            ui.label("y:");
+            ui.add(egui::DragValue::new(&mut self.heatmap_y_range.0).range(yb.min..=yb.max).speed(0.1));
+            ui.add(egui::DragValue::new(&mut self.heatmap_y_range.1).range(yb.min..=yb.max).speed(0.1));
+            let valid = self.heatmap_x_range.0 < self.heatmap_x_range.1 && self.heatmap_y_range.0 < self.heatmap_y_range.1;
+            if valid && (ui.button("Sample").clicked() || self.heatmap.is_none()) {
+                self.run_heatmap(ui.ctx());
+            }
+            if !valid {
+                ui.colored_label(Color32::RED, "each range needs min < max");
+            }
+        });
+
+        let Some(heatmap) = &self.heatmap else { return };
+        let zb = &self.heatmap_fun.z_bounds;
+        let num_reverts = heatmap.grid.values.iter().filter(|v| v.is_err()).count();
+        match heatmap.grid.range() {
+            Some((lo, hi)) => ui.label(format!(
+                "Colour from {:.6e} (blue) to {:.6e} (yellow), {num_reverts} reverted cells in red",
+                u256_to_f64(lo, zb.radix, zb.places), u256_to_f64(hi, zb.radix, zb.places))),
+            None => ui.colored_label(Color32::RED, "Every cell reverted"),
+        };
+
+        let ((x_min, x_max), (y_min, y_max)) = (heatmap.x_range, heatmap.y_range);
+        let (num_x, num_y) = (heatmap.grid.xs.len(), heatmap.grid.ys.len());
+        let hovered = Plot::new("heatmap_plot")
+            .x_axis_label("x")
+            .y_axis_label("y")
+            .data_aspect(((x_max - x_min) / (y_max - y_min)) as f32)
+            .height(ui.available_height() - 2.0 * ui.spacing().interact_size.y)
+            .show(ui, |plot_ui| {
+                let centre = PlotPoint::new((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+                let size = [(x_max - x_min) as f32, (y_max - y_min) as f32];
+                plot_ui.image(PlotImage::new(self.heatmap_fun.name, &heatmap.texture, centre, size));
+                let cell = |p: f64, min: f64, max: f64, n: usize| {
+                    let i = ((p - min) / (max - min) * n as f64).floor();
+                    (i >= 0.0 && i < n as f64).then_some(i as usize)
+                };
+                plot_ui.pointer_coordinate().and_then(|p| Some((cell(p.x, x_min, x_max, num_x)?, cell(p.y, y_min, y_max, num_y)?)))
+            })
+            .inner;
+
+        // Readout of the hovered cell
+        let text = match hovered {
+            Some((column, row)) => {
+                let (x, y) = (heatmap.grid.xs_raw[column], heatmap.grid.ys_raw[row]);
+                match heatmap.grid.get(column, row) {
+                    Ok(z) => format!("f({x}, {y}) = {z} ({})", u256_to_f64(*z, zb.radix, zb.places)),
+                    Err(message) => format!("f({x}, {y}) reverted: {message}"),
+                }
+            }
+            None => "Hover over a cell to read its value".to_string(),
+        };
+        ui.label(text);
+    }
+
+    fn select_outputs_function(&mut self, fun: &'static FixedPointFunctionMulti) {
+        self.outputs_fun = fun;
+        self.outputs_visible = vec![true; fun.outputs.len()];
+        self.outputs_num_points = fun.num_points.default;
+        self.outputs_x_range = (fun.x_bounds.min, fun.x_bounds.max);
+        self.outputs_parametric = self.outputs_parametric.map(|_| (0, 1));
+    }
+
+    fn outputs_window(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut selected = None;
+            egui::ComboBox::from_id_salt("outputs_function")
+                .selected_text(self.outputs_fun.name)
+                .show_ui(ui, |ui| {
+                    for &fun in ALL_MULTI {
+                        if ui.selectable_label(fun.name == self.outputs_fun.name, fun.name).clicked() {
+                            selected = Some(fun);
+                        }
+                    }
+                });
+            if let Some(fun) = selected {
+                self.select_outputs_function(fun);
+            }
+            let np = &self.outputs_fun.num_points;
+            ui.add(Slider::new(&mut self.outputs_num_points, np.min..=np.max).logarithmic(true).text("points"));
+            let xb = &self.outputs_fun.x_bounds;
+            ui.label(if self.outputs_parametric.is_some() { "t:" } else { "x:" });
+            ui.add(egui::DragValue::new(&mut self.outputs_x_range.0).range(xb.min..=xb.max).speed(0.1));
+            ui.add(egui::DragValue::new(&mut self.outputs_x_range.1).range(xb.min..=xb.max).speed(0.1));
+        });
+        if !self.outputs_fun.params.is_empty() {
+            self.params_row(ui, self.outputs_fun.params);
+        }
+        ui.horizontal(|ui| {
+            let outputs = self.outputs_fun.outputs;
+            let mut parametric = self.outputs_parametric.is_some();
+            ui.add_enabled(outputs.len() >= 2, egui::Checkbox::new(&mut parametric, "Parametric"))
+                .on_hover_text("Plot one output against another as the input t sweeps its range");
+            self.outputs_parametric = match (parametric, self.outputs_parametric) {
+                (true, None) => Some((0, 1)),
+                (false, _) => None,
+                (true, axes) => axes,
+            };
+            ui.separator();
+            if let Some((a, b)) = &mut self.outputs_parametric {
+                for (label, axis) in [("horizontal:", a), ("vertical:", b)] {
+                    ui.label(label);
+                    egui::ComboBox::from_id_salt(("parametric_axis", label))
+                        .selected_text(outputs[*axis].name)
+                        .show_ui(ui, |ui| {
+                            for (i, output) in outputs.iter().enumerate() {
+                                ui.selectable_value(axis, i, output.name);
+                            }
+                        });
+                }
+            } else {
+                ui.label("Show:");
+                for (i, (output, visible)) in outputs.iter().zip(&mut self.outputs_visible).enumerate() {
+                    let color = PATH_COLORS[i % PATH_COLORS.len()];
+                    ui.checkbox(visible, egui::RichText::new(output.name).color(color));
+                }
+            }
+        });
+
+        let (x_min, x_max) = self.outputs_x_range;
+        if x_min >= x_max {
+            ui.colored_label(Color32::RED, "the range needs min < max");
+            return;
+        }
+        let fun = self.outputs_fun;
+        let samples = sample_outputs(fun, self.outputs_num_points, x_min, x_max);
+        let num_reverts = samples.iter().filter(|s| s.outputs.is_err()).count();
+        if let Some((x, message)) = samples.iter().find_map(|s| s.outputs.as_ref().err().map(|m| (s.x_raw, m))) {
+            ui.colored_label(Color32::RED, format!("{num_reverts} inputs reverted, first at {x}: {message}"));
+        }
+        if let Some(axes) = self.outputs_parametric {
+            parametric_plot(ui, fun, &samples, axes);
+            return;
+        }
+        // One plot per output, each over its own bounds, sharing the x axis
+        let visible: Vec<usize> = (0..fun.outputs.len()).filter(|&i| self.outputs_visible[i]).collect();
+        let height = ui.available_height() / visible.len().max(1) as f32 - ui.spacing().item_spacing.y;
+        for i in visible {
+            let (output, color) = (&fun.outputs[i], PATH_COLORS[i % PATH_COLORS.len()]);
+            let b = &output.bounds;
+            Plot::new(("outputs_plot", i))
+                .link_axis("outputs_x_axis", [true, false])
+                .default_y_bounds(b.min, b.max)
+                .y_axis_label(output.name)
+                .height(height)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    let series: Vec<[f64; 2]> = samples.iter()
+                        .filter_map(|s| s.outputs.as_ref().ok().map(|ys| [s.x, u256_to_f64(ys[i], b.radix, b.places)]))
+                        .collect();
+                    plot_ui.points(Points::new(output.name, series).color(color).radius(2.0));
+                    let reverted: Vec<[f64; 2]> = samples.iter().filter(|s| s.outputs.is_err()).map(|s| [s.x, b.min]).collect();
+                    if !reverted.is_empty() {
+                        plot_ui.points(Points::new("reverted", reverted).color(Color32::RED).radius(3.0));
+                    }
+                });
+        }
+    }
+
+    fn run_scenario(&mut self) {
+        let amount = |src: &str| -> Result<U256, String> {
+            rational::bigint_to_u256(&expr::parse_constant(src)?).ok_or(format!("{src} doesn't fit in a U256"))
+        };
+        let parsed = || -> Result<(Vec<scenario::Step>, U256, U256), String> {
+            let steps = self.scenario_steps.iter()
+                .map(|(operation, src)| Ok(scenario::Step { operation: *operation, amount: amount(src)? }))
+                .collect::<Result<Vec<scenario::Step>, String>>()?;
+            Ok((steps, amount(&self.scenario_min)?, amount(&self.scenario_max)?))
+        };
+        let (steps, lo, hi) = match parsed() {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                self.scenario_error = Some(err);
+                return;
+            }
+        };
+        if steps.is_empty() || lo >= hi {
+            self.scenario_error = Some("needs at least one step and min < max".to_string());
+            return;
+        }
+        // Evenly spaced from `lo` to exactly `hi`, multiplying before dividing in 512 bits
+        let n = primitive_types::U512::from(self.scenario_points - 1);
+        self.scenario_amounts = (0..self.scenario_points)
+            .map(|i| lo + U256::try_from((hi - lo).full_mul(U256::from(i)) / n).expect("at most hi - lo"))
+            .collect();
+        self.scenario_runs = scenario::sweep(&curve_pool, &steps, self.scenario_varied, &self.scenario_amounts);
+        self.scenario_error = None;
+    }
+
+    fn scenario_window(&mut self, ui: &mut egui::Ui) {
+        let operations = curve_pool.operations;
+        let mut remove = None;
+        egui::Grid::new("scenario_steps").striped(true).show(ui, |ui| {
+            ui.strong("step");
+            ui.strong("operation");
+            ui.strong("amount (raw)");
+            ui.strong("varied");
+            ui.end_row();
+            for (i, (operation, amount)) in self.scenario_steps.iter_mut().enumerate() {
+                ui.label(i.to_string());
+                egui::ComboBox::from_id_salt(("scenario_operation", i))
+                    .selected_text(operations[*operation].name)
+                    .show_ui(ui, |ui| {
+                        for (j, op) in operations.iter().enumerate() {
+                            ui.selectable_value(operation, j, op.name);
+                        }
+                    });
+                ui.add_enabled(self.scenario_varied != i, egui::TextEdit::singleline(amount));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.scenario_varied, i, "");
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        if let Some(i) = remove {
+            self.scenario_steps.remove(i);
+            self.scenario_varied = self.scenario_varied.min(self.scenario_steps.len().saturating_sub(1));
+        }
+        if ui.button("Add step").clicked() {
+            self.scenario_steps.push((0, "0".to_string()));
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Varied amount from");
+            ui.text_edit_singleline(&mut self.scenario_min);
+            ui.label("to");
+            ui.text_edit_singleline(&mut self.scenario_max);
+        });
+        ui.horizontal(|ui| {
+            ui.add(Slider::new(&mut self.scenario_points, 2..=1000).logarithmic(true).text("points"));
+            ui.label("Plot:");
+            egui::ComboBox::from_id_salt("scenario_variable")
+                .selected_text(curve_pool.variables[self.scenario_variable].name)
+                .show_ui(ui, |ui| {
+                    for (i, variable) in curve_pool.variables.iter().enumerate() {
+                        ui.selectable_value(&mut self.scenario_variable, i, variable.name);
+                    }
+                });
+            if ui.button("Run").clicked() {
+                self.run_scenario();
+            }
+        });
+        if let Some(err) = &self.scenario_error {
+            ui.colored_label(Color32::RED, err);
+        }
+
+        let num_reverts = self.scenario_runs.iter().filter(|r| r.revert.is_some()).count();
+        if let Some((step, message)) = self.scenario_runs.iter().find_map(|r| r.revert.as_ref()) {
+            ui.colored_label(Color32::RED, format!("{num_reverts} runs reverted, first at step {step}: {message}"));
+        }
+
+        let places = curve_pool.places;
+        let display = |v: U256| u256_to_f64(v, 10, places);
+        let (variable, v) = (&curve_pool.variables[self.scenario_variable], self.scenario_variable);
+        let variable_reverts = self.scenario_runs.iter().flat_map(|r| &r.values).filter_map(|values| values[v].as_ref().err());
+        if let Some(message) = variable_reverts.clone().next() {
+            ui.colored_label(Color32::RED, format!("{} reverted after {} steps: {message}", variable.name, variable_reverts.count()));
+        }
+        let plot = Plot::new("scenario_plot")
+            .legend(Legend::default())
+            .x_axis_label(format!("amount of step {}", self.scenario_varied))
+            .y_axis_label(variable.name);
+        plot.show(ui, |plot_ui| {
+            let num_steps = self.scenario_runs.iter().map(|r| r.states.len()).max().unwrap_or(0);
+            for step in 0..num_steps {
+                let series: Vec<[f64; 2]> = self.scenario_amounts.iter().zip(&self.scenario_runs)
+                    .filter_map(|(&x, r)| Some([display(x), display(*r.values.get(step)?[v].as_ref().ok()?)]))
+                    .collect();
+                let name = format!("after step {step}: {}", operations[self.scenario_steps.get(step).map_or(0, |s| s.0)].name);
+                plot_ui.line(Line::new(name, PlotPoints::new(series)));
+            }
+            // Runs that reverted, or where the variable itself reverted after some step
+            let reverted: Vec<[f64; 2]> = self.scenario_amounts.iter().zip(&self.scenario_runs)
+                .filter(|(_, r)| r.revert.is_some() || r.values.iter().any(|values| values[v].is_err()))
+                .map(|(&x, _)| [display(x), 0.0])
+                .collect();
+            plot_ui.points(Points::new("reverted", PlotPoints::new(reverted)).color(Color32::RED).radius(3.0));
+        });
+    }
+
+    fn stats_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Statistics").default_open(true).show(ui, |ui| {
+            let Some(stats) = &self.stats else { return };
+            let mut jump = None;
+            egui::Grid::new("stats").striped(true).show(ui, |ui| {
+                ui.label("samples");
+                ui.label(stats.num_samples.to_string());
+                ui.end_row();
+                ui.label("distinct inputs");
+                ui.label(stats.distinct_inputs.to_string());
+                ui.end_row();
+                ui.label("sampling time");
+                ui.label(format!("{:.2} ms", stats.sampling_time.as_secs_f64() * 1000.0));
+                ui.end_row();
+                for (name, extreme) in [("min y", stats.min_y), ("max y", stats.max_y)] {
+                    ui.label(name);
+                    match extreme {
+                        None => { ui.label("-"); }
+                        Some((x, y)) => {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{y} at"));
+                                if ui.link(x.to_string()).on_hover_text("Jump to this input").clicked() {
+                                    jump = Some(x);
+                                }
+                            });
+                        }
+                    }
+                    ui.end_row();
+                }
+                ui.label("reverts");
+                ui.label(stats.num_reverts().to_string());
+                ui.end_row();
+                for (message, count) in &stats.reverts {
+                    ui.label(format!("  {message}"));
+                    ui.label(count.to_string());
+                    ui.end_row();
+                }
+            });
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
+
+    fn boundary_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Revert boundaries").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Locate in view").clicked() {
+                    self.revert_boundaries = locate_revert_boundaries(self.fun, &self.samples);
+                }
+                if ui.button("Clear").clicked() {
+                    self.revert_boundaries.clear();
+                }
+            });
+
+            let mut jump = None;
+            for boundary in &self.revert_boundaries {
+                ui.horizontal(|ui| {
+                    let (text, before) = if boundary.starts_reverting {
+                        ("first failing", "last succeeding")
+                    } else {
+                        ("first succeeding", "last failing")
+                    };
+                    ui.label(format!("{text}:")).on_hover_text(format!("{before}: {}", boundary.before));
+                    if ui.link(boundary.after.to_string()).on_hover_text("Jump to this input").clicked() {
+                        jump = Some(boundary.after);
+                    }
+                });
+            }
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
+
+    fn plateau_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Plateaus").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Detect in view").clicked() {
+                    self.plateaus = detect_plateaus(self.fun, &self.samples);
+                }
+                if ui.button("Clear").clicked() {
+                    self.plateaus.clear();
+                }
+            });
+            if self.plateaus.is_empty() {
+                return;
+            }
+
+            let zero_width = self.plateaus.iter()
+                .filter(|p| p.y.is_zero())
+                .fold(U256::zero(), |total, p| total.saturating_add(p.width()));
+            ui.label(format!("{} plateaus, {zero_width} inputs give 0", self.plateaus.len()));
+
+            // Widest first, since those are the inputs that change the output the least
+            let mut order: Vec<usize> = (0..self.plateaus.len()).collect();
+            order.sort_by_key(|&i| std::cmp::Reverse(self.plateaus[i].width()));
+
+            let mut jump = None;
+            egui::Grid::new("plateaus").striped(true).show(ui, |ui| {
+                ui.strong("start");
+                ui.strong("width");
+                ui.strong("y");
+                ui.end_row();
+                for &i in order.iter().take(MAX_LISTED) {
+                    let p = &self.plateaus[i];
+                    if ui.link(p.start.to_string()).on_hover_text(format!("Jump to this input\nend: {}", p.end)).clicked() {
+                        jump = Some(p.start);
+                    }
+                    // Plateaus cut off by the sampled range are at least this wide
+                    let at_least = if p.start_exact && p.end_exact { "" } else { "≥ " };
+                    ui.label(format!("{at_least}{}", p.width()));
+                    ui.label(p.y.to_string());
+                    ui.end_row();
+                }
+            });
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
+
+    fn anomaly_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Anomalies").default_open(true).show(ui, |ui| {
+            ui.checkbox(&mut self.show_anomalies, "Detect outliers, jumps and collapses to 0");
+            if !self.show_anomalies {
+                return;
+            }
+            ui.add(Slider::new(&mut self.anomaly_threshold, 2.0..=1000.0).logarithmic(true)
+                .text("× typical change"));
+            ui.label(format!("{} anomalies", self.anomalies.len()));
+
+            let mut jump = None;
+            egui::Grid::new("anomalies").striped(true).show(ui, |ui| {
+                for a in self.anomalies.iter().take(MAX_LISTED) {
+                    ui.label(a.kind.label());
+                    ui.label(format!("{:.1}×", a.severity));
+                    if ui.link(a.x_raw.to_string()).on_hover_text("Jump to this input").clicked() {
+                        jump = Some(a.x_raw);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
+
+    fn distribution_panel(&mut self, ui: &mut egui::Ui) {
+        ui.add(Slider::new(&mut self.histogram_bins, 5..=100).text("bins"));
+
+        ui.heading(format!("{} error", self.error_unit.label()));
+        if self.fun.reference.is_none() {
+            ui.label("No reference to compare against");
+        } else {
+            let yb = &self.fun.y_bounds;
+            let errors: Vec<f64> = self.samples.iter()
+                .filter_map(|s| Some(error_in(self.error_unit, s.y_raw?, s.reference.as_ref()?, yb)))
+                .collect();
+            match distribution::summarise(&errors) {
+                None => { ui.label("No samples with a value"); }
+                Some(summary) => {
+                    egui::Grid::new("error_summary").show(ui, |ui| {
+                        for (name, value) in [("mean", summary.mean), ("median", summary.median), ("std dev", summary.stddev)] {
+                            ui.label(name);
+                            ui.monospace(format!("{value:+.6e}"));
+                            ui.end_row();
+                        }
+                    });
+                    // The mean's standard error tells a systematic bias from noise around zero
+                    let standard_error = summary.stddev / (summary.count as f64).sqrt();
+                    if summary.mean.abs() > 3.0 * standard_error {
+                        let direction = if summary.mean > 0.0 { "up" } else { "down" };
+                        ui.colored_label(Color32::RED, format!("Biased {direction} (mean > 3 standard errors)"));
+                    }
+                }
+            }
+            let histogram = Histogram::new(&errors, self.histogram_bins);
+            histogram_plot("error_histogram", "error").show(ui, |plot_ui| {
+                plot_ui.bar_chart(histogram_chart("error", &histogram, Color32::DARK_RED));
+            });
+        }
+
+        let yb = &self.fun.y_bounds;
+        ui.heading(format!("y mod {}^{}", yb.radix, self.residue_digits));
+        ui.add(Slider::new(&mut self.residue_digits, 1..=yb.places.max(1)).text("digits"));
+        let residues = distribution::residues(self.samples.iter().filter_map(|s| s.y_raw), yb.radix, self.residue_digits);
+        let values: Vec<f64> = residues.iter().map(|r| r.to_string().parse().unwrap_or(f64::NAN)).collect();
+        // One bin per residue when there are few enough of them
+        let modulus = (yb.radix as f64).powi(self.residue_digits as i32);
+        let bins = if modulus <= self.histogram_bins as f64 { modulus as usize } else { self.histogram_bins };
+        let histogram = Histogram::over(&values, 0.0, modulus, bins);
+        histogram_plot("residue_histogram", "residue").show(ui, |plot_ui| {
+            plot_ui.bar_chart(histogram_chart("residue", &histogram, Color32::DARK_BLUE));
+        });
+    }
+
+    fn check_monotonicity(&mut self, monotonicity: Monotonicity) {
+        let mut violations = monotonic::scan_samples(monotonicity, &self.samples);
+        if self.neighbourhood_radius > 0 {
+            violations.extend(monotonic::scan_neighbourhoods(self.fun, monotonicity, &self.samples, self.neighbourhood_radius));
+        }
+
+        let mut minimal: Vec<monotonic::Violation> = violations.iter()
+            .take(MAX_MINIMISED_VIOLATIONS)
+            .map(|v| monotonic::minimise(self.fun, monotonicity, v))
+            .collect();
+        minimal.sort_by_key(|v| (v.x0, v.x1));
+        minimal.dedup_by_key(|v| (v.x0, v.x1));
+
+        self.monotonicity_violations = violations;
+        self.minimal_violations = minimal;
+    }
+
+    fn monotonicity_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Monotonicity").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let direction = self.expected_monotonicity.map(|m| m.direction);
+                let text = match direction {
+                    None => "not expected",
+                    Some(Direction::Increasing) => "increasing",
+                    Some(Direction::Decreasing) => "decreasing",
+                };
+                let strict = self.expected_monotonicity.is_some_and(|m| m.strict);
+                egui::ComboBox::from_id_salt("monotonicity").selected_text(text).show_ui(ui, |ui| {
+                    if ui.selectable_label(direction.is_none(), "not expected").clicked() {
+                        self.expected_monotonicity = None;
+                    }
+                    for (d, label) in [(Direction::Increasing, "increasing"), (Direction::Decreasing, "decreasing")] {
+                        if ui.selectable_label(direction == Some(d), label).clicked() {
+                            self.expected_monotonicity = Some(Monotonicity { direction: d, strict });
+                        }
+                    }
+                });
+                if let Some(m) = &mut self.expected_monotonicity {
+                    ui.checkbox(&mut m.strict, "strict");
+                }
+            });
+
+            let Some(monotonicity) = self.expected_monotonicity else { return };
+            ui.add(Slider::new(&mut self.neighbourhood_radius, 0..=64).text("neighbours checked per sample"));
+            ui.horizontal(|ui| {
+                if ui.button("Check").clicked() {
+                    self.check_monotonicity(monotonicity);
+                }
+                if ui.button("Clear").clicked() {
+                    self.monotonicity_violations.clear();
+                    self.minimal_violations.clear();
+                }
+            });
+
+            if !self.monotonicity_violations.is_empty() {
+                ui.label(format!("{} violating pairs, minimal counterexamples:", self.monotonicity_violations.len()));
+            }
+            let mut jump = None;
+            for v in &self.minimal_violations {
+                ui.horizontal(|ui| {
+                    if ui.link(v.x0.to_string()).on_hover_text("Jump to this input").clicked() {
+                        jump = Some(v.x0);
+                    }
+                    ui.label(format!("f = {}, then {} at +{}", v.y0, v.y1, v.x1 - v.x0));
+                });
+            }
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
+
+    fn invariant_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Invariants").default_open(true).show(ui, |ui| {
+            let mut remove = None;
+            for (i, invariant) in self.invariants.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.monospace(&invariant.source);
+                    match &invariant.expr {
+                        Err(err) => { ui.colored_label(Color32::RED, err); }
+                        Ok(_) => {
+                            let violations = self.invariant_violations.iter().filter(|v| v.invariant == i).count();
+                            let color = if violations > 0 { Color32::RED } else { Color32::DARK_GREEN };
+                            ui.colored_label(color, format!("{violations} violations"));
+                            if let Some(&errors) = self.invariant_errors.get(i) && errors > 0 {
+                                ui.label(format!("({errors} not evaluated)"));
+                            }
+                        }
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                remove_invariant(&mut self.invariants, &mut self.invariant_violations, &mut self.invariant_errors, i);
+            }
+
+            ui.horizontal(|ui| {
+                let edit = ui.text_edit_singleline(&mut self.new_invariant)
+                    .on_hover_text("e.g. y <= x, y == 0 implies x < 1000. Variables: x, y, ref, g (raw units)");
+                let entered = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button("Add").clicked() || entered) && !self.new_invariant.trim().is_empty() {
+                    self.invariants.push(Invariant::new(self.new_invariant.trim()));
+                    self.new_invariant.clear();
+                }
+            });
+
+            let mut jump = None;
+            egui::Grid::new("invariant_violations").striped(true).show(ui, |ui| {
+                for v in self.invariant_violations.iter().take(MAX_LISTED) {
+                    if ui.link(v.x_raw.to_string()).on_hover_text("Jump to this input").clicked() {
+                        jump = Some(v.x_raw);
+                    }
+                    ui.label(v.y_raw.to_string());
+                    ui.monospace(self.invariants.get(v.invariant).map_or("", |i| i.source.as_str()));
+                    ui.end_row();
+                }
+            });
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
+
+    fn rounding_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Rounding direction").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Should round:");
+                ui.radio_value(&mut self.rounding, None, "any");
+                for r in [Rounding::Down, Rounding::Up] {
+                    ui.radio_value(&mut self.rounding, Some(r), r.label());
+                }
+            });
+            let Some(rounding) = self.rounding else { return };
+            if self.fun.reference.is_none() {
+                ui.label("Needs a reference to compare against");
+                return;
+            }
+            ui.checkbox(&mut self.check_rounding, "Check against the reference")
+                .on_hover_text("Computes the reference at every sample, which can be slow");
+            if !self.check_rounding {
+                return;
+            }
+
+            let total = rounding::total_amount(&self.wrong_rounding);
+            let yb = &self.fun.y_bounds;
+            let total_real = (&total / &Rational::scale(yb.radix, yb.places)).to_f64();
+            let color = if self.wrong_rounding.is_empty() { Color32::DARK_GREEN } else { Color32::RED };
+            ui.colored_label(color, format!("{} samples round {} of the reference", self.wrong_rounding.len(),
+                if rounding == Rounding::Down { "above" } else { "below" }));
+            ui.label(format!("Total wrong-direction value: {:.3} raw ({total_real:e})", total.to_f64()));
+
+            let worst = self.wrong_rounding.iter().max_by(|a, b| a.amount.cmp(&b.amount)).map(|w| (w.x_raw, w.amount.to_f64()));
+            if let Some((x_raw, amount)) = worst {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Worst: {amount:.3} raw at"));
+                    if ui.link(x_raw.to_string()).on_hover_text("Jump to this input").clicked() {
+                        self.jump_to(x_raw);
+                    }
+                });
+            }
+        });
+    }
+
+    /// The derivative's step, if the derivative is shown
+    fn derivative_step(&self) -> Result<Option<Step>, String> {
+        if !self.show_derivative {
+            return Ok(None);
+        }
+        match self.derivative_step {
+            StepKind::Wei => Ok(Some(Step::Wei)),
+            StepKind::Spacing => Ok(Some(Step::Spacing)),
+            StepKind::Custom => {
+                let h = expr::parse_constant(&self.derivative_custom_step)?;
+                match rational::bigint_to_u256(&h) {
+                    Some(h) if !h.is_zero() => Ok(Some(Step::Raw(h))),
+                    _ => Err("h must be a positive U256".to_string()),
+                }
+            }
+        }
+    }
 
-    current_bounds: Option<egui_plot::PlotBounds>,
-    reset_view: bool,
-    num_points: usize,
-    error_message: Option<String>,
-    last_error_x: Option<f64>,
-    fps: f32,
-    frame_times: Vec<f64>,
-    last_frame_time: std::time::Instant,
-}
+    /// The metamorphic relation configured in the tools panel
+    fn relation(&self) -> Result<Option<Relation>, String> {
+        let constant = |src: &str| expr::parse_constant(src);
+        match self.relation_kind {
+            RelationKind::None => Ok(None),
+            RelationKind::Scaling => {
+                let k = rational::bigint_to_u256(&constant(&self.relation_k)?).ok_or("k must fit in a U256")?;
+                Ok(Some(Relation::Scaling { k, tolerance: constant(&self.relation_tolerance)? }))
+            }
+            RelationKind::Increment => Ok(Some(Relation::Increment {
+                min: constant(&self.relation_min)?,
+                max: constant(&self.relation_max)?,
+            })),
+            RelationKind::Inverse => match self.relation_inverse {
+                Some(inverse) => Ok(Some(Relation::Inverse { inverse, tolerance: constant(&self.relation_tolerance)? })),
+                None => Err("select an inverse function".to_string()),
+            },
+        }
+    }
 
-impl Default for EllipticApp {
-    fn default() -> Self {
-        /* These bounds must be pre-divided by radix^places */
-        let (xb, yb) = (FIXED_POINT_FUN.x_bounds, FIXED_POINT_FUN.y_bounds);
-        let (x_min, x_max, y_min, y_max) = (xb.min, xb.max, yb.min, yb.max);
-        Self {
-            // Initialize sampling bounds
-            sampling_x_min: x_min,
-            sampling_x_max: x_max,
+    fn relation_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Metamorphic relation").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.relation_kind, RelationKind::None, "none");
+                ui.radio_value(&mut self.relation_kind, RelationKind::Scaling, "f(kx) ≈ kf(x)");
+                ui.radio_value(&mut self.relation_kind, RelationKind::Increment, "f(x+1) - f(x)");
+                ui.radio_value(&mut self.relation_kind, RelationKind::Inverse, "g(f(x)) ≈ x");
+            });
+            match self.relation_kind {
+                RelationKind::None => return,
+                RelationKind::Scaling => {
+                    ui.horizontal(|ui| {
+                        ui.label("k:");
+                        ui.text_edit_singleline(&mut self.relation_k);
+                    });
+                }
+                RelationKind::Increment => {
+                    ui.horizontal(|ui| {
+                        ui.label("min:");
+                        ui.text_edit_singleline(&mut self.relation_min);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("max:");
+                        ui.text_edit_singleline(&mut self.relation_max);
+                    });
+                }
+                RelationKind::Inverse => {
+                    let fun = self.fun;
+                    egui::ComboBox::from_id_salt("inverse_function")
+                        .selected_text(self.relation_inverse.map_or("select g", |g| g.name))
+                        .show_ui(ui, |ui| {
+                            for &g in functions::ALL.iter().filter(|g| fun.is_invertible_by(g)) {
+                                let is_selected = self.relation_inverse.is_some_and(|s| s.name == g.name);
+                                if ui.selectable_label(is_selected, g.name).clicked() {
+                                    self.relation_inverse = Some(g);
+                                }
+                            }
+                        });
+                    ui.checkbox(&mut self.show_round_trip, "Plot round trip g(f(x)) against x");
+                }
+            }
+            if matches!(self.relation_kind, RelationKind::Scaling | RelationKind::Inverse) {
+                ui.horizontal(|ui| {
+                    ui.label("tolerance (raw):");
+                    ui.text_edit_singleline(&mut self.relation_tolerance);
+                });
+            }
+            match self.relation() {
+                Err(err) => { ui.colored_label(Color32::RED, err); }
+                Ok(_) => {
+                    let color = if self.num_relation_violations > 0 { Color32::RED } else { Color32::DARK_GREEN };
+                    ui.colored_label(color, format!("{} violating samples", self.num_relation_violations));
+                }
+            }
+        });
+    }
 
-            // Initialize display bounds
-            display_x_min: x_min,
-            display_x_max: x_max,
-            display_y_min: y_min,
-            display_y_max: y_max,
+    fn profit_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Round-trip profit").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Follow with:");
+                let fun = self.fun;
+                egui::ComboBox::from_id_salt("profit_inverse")
+                    .selected_text(self.profit_inverse.map_or("select g", |g| g.name))
+                    .show_ui(ui, |ui| {
+                        for &g in functions::ALL.iter().filter(|g| fun.is_invertible_by(g)) {
+                            let is_selected = self.profit_inverse.is_some_and(|s| s.name == g.name);
+                            if ui.selectable_label(is_selected, g.name).clicked() {
+                                self.profit_inverse = Some(g);
+                            }
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Profit:");
+                ui.text_edit_singleline(&mut self.profit_expression)
+                    .on_hover_text("Variables: x, y = f(x) and z = g(y), in raw units");
+            });
+            ui.checkbox(&mut self.show_profit, "Plot profit against x");
+            ui.horizontal(|ui| {
+                if ui.button("Search for profit").on_hover_text("Uses the max-error search's budget").clicked() {
+                    self.run_profit_search();
+                }
+                if ui.button("Clear").clicked() {
+                    self.profit_results.clear();
+                }
+            });
+            if let Err(err) = self.round_trip() {
+                ui.colored_label(Color32::RED, err);
+            } else if let Some(err) = &self.profit_error {
+                ui.colored_label(Color32::RED, err);
+            }
 
-            // Initialize input fields
-            sampling_x_min_input: x_min.to_string(),
-            sampling_x_max_input: x_max.to_string(),
-            display_x_min_input: x_min.to_string(),
-            display_x_max_input: x_max.to_string(),
-            display_y_min_input: y_min.to_string(),
-            display_y_max_input: y_max.to_string(),
+            let exploits: Vec<&Candidate> = self.profit_results.iter()
+                .filter(|c| !c.score.is_negative() && !c.score.is_zero())
+                .collect();
+            if !self.profit_results.is_empty() {
+                if exploits.is_empty() {
+                    let best = self.profit_results[0].score.to_f64();
+                    ui.colored_label(Color32::DARK_GREEN, format!("No profitable input found, best profit {best:.3e}"));
+                } else {
+                    ui.colored_label(Color32::RED, format!("{} profitable inputs found", exploits.len()));
+                }
+            }
+            let mut jump = None;
+            egui::Grid::new("profit_results").striped(true).show(ui, |ui| {
+                if !exploits.is_empty() {
+                    ui.strong("x (raw)");
+                    ui.strong("y (raw)");
+                    ui.strong("profit");
+                    ui.end_row();
+                }
+                for candidate in &exploits {
+                    if ui.link(candidate.x.to_string()).on_hover_text("Jump to this input").clicked() {
+                        jump = Some(candidate.x);
+                    }
+                    ui.label(candidate.y.to_string());
+                    ui.label(candidate.score.floor().to_string());
+                    ui.end_row();
+                }
+            });
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
+    }
 
-            current_bounds: None,
-            reset_view: false,
-            num_points: FIXED_POINT_FUN.num_points.default,
-            error_message: None,
-            last_error_x: None,
-            fps: 0.0,
-            frame_times: Vec::with_capacity(100),
-            last_frame_time: std::time::Instant::now(),
-        }
+    fn search_panel(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Max-error search").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(self.fun.reference.is_some(), |ui| {
+                    ui.radio_value(&mut self.search_objective, SearchObjective::Reference, "vs reference")
+                        .on_hover_text(format!("|error| in {} units", self.error_unit.label()));
+                });
+                ui.add_enabled_ui(self.other_fun.is_some(), |ui| {
+                    ui.radio_value(&mut self.search_objective, SearchObjective::Difference, "vs other");
+                });
+                ui.radio_value(&mut self.search_objective, SearchObjective::Expression, "expression");
+            });
+            if self.search_objective == SearchObjective::Expression {
+                ui.text_edit_singleline(&mut self.search_expression)
+                    .on_hover_text("Maximised over raw inputs. Variables: x, y, ref (reference), g (compared function)");
+            }
+            ui.add(Slider::new(&mut self.search_budget, 100..=200_000).logarithmic(true).text("evaluations"));
+            ui.add(Slider::new(&mut self.search_top_k, 1..=50).text("results"));
+            if ui.button("Search").clicked() {
+                self.run_search();
+            }
+            if let Some(err) = &self.search_error {
+                ui.colored_label(Color32::RED, err);
+            }
+
+            let mut jump = None;
+            egui::Grid::new("search_results").striped(true).show(ui, |ui| {
+                if !self.search_results.is_empty() {
+                    ui.strong("x (raw)");
+                    ui.strong("y (raw)");
+                    ui.strong("objective");
+                    ui.end_row();
+                }
+                for candidate in &self.search_results {
+                    if ui.link(candidate.x.to_string()).on_hover_text("Jump to this input").clicked() {
+                        jump = Some(candidate.x);
+                    }
+                    ui.label(candidate.y.to_string());
+                    ui.label(format!("{:.6e}", candidate.score.to_f64()));
+                    ui.end_row();
+                }
+            });
+            if let Some(x) = jump {
+                self.jump_to(x);
+            }
+        });
     }
 }
 
@@ -101,7 +1740,30 @@ impl eframe::App for EllipticApp {
         ctx.request_repaint();
         egui::TopBottomPanel::top("input_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.heading(FIXED_POINT_FUN.name);
+                let (mut selected, mut selected_n) = (None, None);
+                egui::ComboBox::from_id_salt("function")
+                    .selected_text(egui::RichText::new(self.fun.name).heading())
+                    .show_ui(ui, |ui| {
+                        for &fun in functions::ALL {
+                            if ui.selectable_label(fun.name == self.fun.name, fun.name).clicked() {
+                                selected = Some(fun);
+                            }
+                        }
+                        ui.separator();
+                        for &fun in functions::ALL_N {
+                            let is_selected = self.slice.is_some_and(|(current, _)| current.name == fun.name);
+                            if ui.selectable_label(is_selected, fun.name).clicked() && !is_selected {
+                                selected_n = Some(fun);
+                            }
+                        }
+                    });
+                if let Some(fun) = selected.filter(|fun| fun.name != self.fun.name) {
+                    self.slice = None;
+                    self.select_function(fun);
+                }
+                if let Some(fun) = selected_n {
+                    self.select_slice(fun, 0);
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
                     ui.label(format!("FPS: {:.1}", self.fps));
                 });
@@ -121,10 +1783,9 @@ impl eframe::App for EllipticApp {
                     ui.text_edit_singleline(&mut self.sampling_x_max_input);
                 });
 
-                if ui.button("Set").clicked() {
-                    if let Ok(value) = self.sampling_x_max_input.parse::<f64>() {
-                        self.sampling_x_max = value;
-                    }
+                if ui.button("Set").clicked()
+                    && let Ok(value) = self.sampling_x_max_input.parse::<f64>() {
+                    self.sampling_x_max = value;
                 }
             });
 
@@ -138,13 +1799,11 @@ impl eframe::App for EllipticApp {
                     ui.label("X Min:");
                     ui.label(format!("{:.2e}", self.display_x_min));
                     ui.text_edit_singleline(&mut self.display_x_min_input);
-                    if ui.button("Set").clicked() {
-                        if let Ok(value) = self.display_x_min_input.parse::<f64>() {
-                            if self.display_x_min != value {
-                                self.display_x_min = value;
-                                self.reset_view = true;
-                            }
-                        }
+                    if ui.button("Set").clicked()
+                        && let Ok(value) = self.display_x_min_input.parse::<f64>()
+                        && self.display_x_min != value {
+                        self.display_x_min = value;
+                        self.reset_view = true;
                     }
 
                     ui.label("X Max:");
@@ -162,20 +1821,19 @@ impl eframe::App for EllipticApp {
                     ui.text_edit_singleline(&mut self.display_y_max_input);
                 });
 
-                if ui.button("Set").clicked() {
-                        if let (Ok(y_min_val), Ok(y_max_val), Ok(x_min_val), Ok(x_max_val)) =
-                               (self.display_y_min_input.parse::<f64>(),
-                                self.display_y_max_input.parse::<f64>(),
-                                self.display_x_min_input.parse::<f64>(),
-                                self.display_x_max_input.parse::<f64>(),)
-                                {
-                            self.display_y_min = y_min_val;
-                            self.display_y_max = y_max_val;
-                            self.display_x_min = x_min_val;
-                            self.display_x_max = x_max_val;
-                            self.reset_view = true;
-                        }
-                    }
+                if ui.button("Set").clicked()
+                    && let (Ok(y_min_val), Ok(y_max_val), Ok(x_min_val), Ok(x_max_val)) =
+                           (self.display_y_min_input.parse::<f64>(),
+                            self.display_y_max_input.parse::<f64>(),
+                            self.display_x_min_input.parse::<f64>(),
+                            self.display_x_max_input.parse::<f64>(),)
+                            {
+                    self.display_y_min = y_min_val;
+                    self.display_y_max = y_max_val;
+                    self.display_x_min = x_min_val;
+                    self.display_x_max = x_max_val;
+                    self.reset_view = true;
+                }
 
                 if ui.button("Reset to Sampling Bounds").clicked() {
                     self.display_x_min = self.sampling_x_min;
@@ -188,13 +1846,105 @@ impl eframe::App for EllipticApp {
 
             // Add slider for number of points
             ui.horizontal(|ui| {
-                let np = FIXED_POINT_FUN.num_points;
+                let np = &self.fun.num_points;
                 ui.label("Number of points:");
                 ui.add(Slider::new(&mut self.num_points, np.min..=np.max)
                     .logarithmic(true)
                     .text("points"));
+                ui.checkbox(&mut self.show_gas, "Show estimated gas");
+                ui.checkbox(&mut self.color_by_path, "Colour by code path");
+                ui.checkbox(&mut self.show_distributions, "Show distributions");
+                ui.checkbox(&mut self.show_scenario, "Scenario");
+                ui.checkbox(&mut self.show_heatmap, "Heatmap");
+                ui.checkbox(&mut self.show_outputs, "Several outputs");
+            });
+
+            // Comparison against the function's ideal reference
+            ui.horizontal(|ui| {
+                ui.add_enabled(self.fun.reference.is_some(),
+                    egui::Checkbox::new(&mut self.show_reference, "Compare with reference"));
+                if self.show_reference {
+                    ui.label("Error:");
+                    for unit in ErrorUnit::ALL {
+                        ui.radio_value(&mut self.error_unit, unit, unit.label());
+                    }
+                    ui.separator();
+                    ui.label("Precision:");
+                    ui.radio_value(&mut self.bits_measure, None, "off");
+                    for measure in BitsMeasure::ALL {
+                        ui.radio_value(&mut self.bits_measure, Some(measure), measure.label());
+                    }
+                    if let (Some(measure), Some((x_raw, bits))) = (self.bits_measure, self.worst_bits) {
+                        ui.label(format!("worst: {bits:.2} {} at", measure.label()));
+                        if ui.link(x_raw.to_string()).on_hover_text("Jump to this input").clicked() {
+                            self.jump_to(x_raw);
+                        }
+                    }
+                }
+            });
+
+            // A slice's parameters are the function's other inputs, which have sliders instead
+            if let Some((fun, axis)) = self.slice {
+                self.slice_row(ui, fun, axis);
+            } else if !self.fun.params.is_empty() {
+                self.params_row(ui, self.fun.params);
+            }
+
+            // Finite-difference derivative
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.show_derivative, "Show derivative (f(x+h) - f(x)) / h");
+                if self.show_derivative {
+                    ui.label("h:");
+                    ui.radio_value(&mut self.derivative_step, StepKind::Wei, "1 wei");
+                    ui.radio_value(&mut self.derivative_step, StepKind::Spacing, "sample spacing");
+                    ui.radio_value(&mut self.derivative_step, StepKind::Custom, "custom (raw)");
+                    if self.derivative_step == StepKind::Custom {
+                        ui.text_edit_singleline(&mut self.derivative_custom_step);
+                    }
+                    if let Err(err) = self.derivative_step() {
+                        ui.colored_label(Color32::RED, err);
+                    }
+                }
+            });
+
+            // Differential comparison against a second implementation
+            ui.horizontal(|ui| {
+                ui.label("Compare with:");
+                let fun = self.fun;
+                egui::ComboBox::from_id_salt("other_function")
+                    .selected_text(self.other_fun.map_or("none", |other| other.name))
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(self.other_fun.is_none(), "none").clicked() {
+                            self.other_fun = None;
+                        }
+                        for &other in functions::ALL.iter()
+                            .filter(|other| other.name != fun.name && fun.is_comparable_with(other)) {
+                            let is_selected = self.other_fun.is_some_and(|o| o.name == other.name);
+                            if ui.selectable_label(is_selected, other.name).clicked() {
+                                self.other_fun = Some(other);
+                            }
+                        }
+                    });
+                if self.other_fun.is_some() {
+                    let text = format!("{} mismatching samples", self.num_mismatches);
+                    let color = if self.num_mismatches > 0 { Color32::RED } else { Color32::DARK_GREEN };
+                    ui.label(egui::RichText::new(text).color(color));
+                }
             });
 
+            // Coverage summary for the current sample set
+            let paths = self.fun.paths;
+            if !paths.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Paths hit: {}/{}", paths.len() - self.paths_not_hit.len(), paths.len()));
+                    if !self.paths_not_hit.is_empty() {
+                        let names: Vec<&str> = self.paths_not_hit.iter().map(|&p| paths[p]).collect();
+                        ui.label(egui::RichText::new("Never hit:").strong());
+                        ui.label(names.join(", "));
+                    }
+                });
+            }
+
             ui.horizontal(|ui| {
                 if let Some(error_msg) = &self.error_message {
                     ui.label(egui::RichText::new("Error:").color(egui::Color32::RED).strong());
@@ -237,14 +1987,64 @@ impl eframe::App for EllipticApp {
             }
         });
 
+        // Window for the stateful scenario
+        let mut show_scenario = self.show_scenario;
+        egui::Window::new(format!("Scenario: {}", curve_pool.name))
+            .open(&mut show_scenario)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| self.scenario_window(ui));
+        self.show_scenario = show_scenario;
+
+        // Window for the heatmap of a two-input function
+        let mut show_heatmap = self.show_heatmap;
+        egui::Window::new(format!("Heatmap: {}", self.heatmap_fun.name))
+            .open(&mut show_heatmap)
+            .default_size([600.0, 650.0])
+            .show(ctx, |ui| self.heatmap_window(ui));
+        self.show_heatmap = show_heatmap;
+
+        // Window for a function with several outputs
+        let mut show_outputs = self.show_outputs;
+        egui::Window::new(format!("Outputs: {}", self.outputs_fun.name))
+            .open(&mut show_outputs)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| self.outputs_window(ui));
+        self.show_outputs = show_outputs;
+
+        // Side panel for the error and residue distributions
+        if self.show_distributions {
+            egui::SidePanel::left("distribution_panel").show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.distribution_panel(ui));
+            });
+        }
+
+        // Side panel for the analysis tools
+        egui::SidePanel::right("tools_panel").show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                self.stats_panel(ui);
+                self.search_panel(ui);
+                self.profit_panel(ui);
+                self.iteration_panel(ui);
+                self.param_search_panel(ui);
+                self.boundary_panel(ui);
+                self.plateau_panel(ui);
+                self.anomaly_panel(ui);
+                self.monotonicity_panel(ui);
+                self.invariant_panel(ui);
+                self.rounding_panel(ui);
+                self.relation_panel(ui);
+            });
+        });
+
         // Central panel for the plot
         egui::CentralPanel::default().show(ctx, |ui| {
-            let mut was_reset = false;
-            if let None = self.current_bounds { was_reset = true }
+            let mut was_reset = self.current_bounds.is_none();
 
             let mut plot = Plot::new("plot")
+                .link_axis("x_axis", [true, false])
                 .default_x_bounds(self.display_x_min, self.display_x_max)
                 .default_y_bounds(self.display_y_min, self.display_y_max)
+                .legend(Legend::default())
                 .auto_bounds(true);
 
             // Get the current x bounds from the plot if available, otherwise use display bounds
@@ -258,7 +2058,25 @@ impl eframe::App for EllipticApp {
             let sample_x_max = base_x_max.max(self.sampling_x_min).min(self.sampling_x_max);
 
             // Sample the curve with panic handling using the current view bounds for x
-            let (points, errorPoints) = sample_curve_u256_safe(self.num_points, sample_x_min, sample_x_max);
+            let show_reference = self.show_reference && self.fun.reference.is_some();
+            let relation = self.relation().ok().flatten();
+            let check_rounding = self.check_rounding && self.rounding.is_some();
+            let needs_reference = (show_reference || check_rounding || self.show_distributions) && self.fun.reference.is_some();
+            let options = SampleOptions { reference: needs_reference, compare_with: self.other_fun, relation: relation.as_ref() };
+            let sampling_start = std::time::Instant::now();
+            let samples = sample_curve_u256_safe(self.fun, &options, self.num_points, sample_x_min, sample_x_max);
+            self.stats = Some(SampleStats::new(&samples, sampling_start.elapsed()));
+            let (points, error_points) = sample_points(&samples);
+            self.num_mismatches = samples.iter().filter(|s| is_mismatch(s)).count();
+            (self.invariant_violations, self.invariant_errors) = check_invariants(&self.invariants, &samples);
+            self.num_relation_violations = relation.as_ref().map_or(0, |relation| {
+                samples.iter().filter(|s| s.deviation.as_ref().is_some_and(|d| relation.is_violated(d))).count()
+            });
+            self.wrong_rounding = self.rounding.filter(|_| check_rounding).map_or(Vec::new(), |r| rounding::check_rounding(r, &samples));
+            self.anomalies = if self.show_anomalies { detect_anomalies(&samples, self.anomaly_threshold) } else { Vec::new() };
+            let bits_measure = self.bits_measure.filter(|_| show_reference);
+            self.worst_bits = bits_measure.and_then(|measure| worst_bits(&samples, measure));
+            self.paths_not_hit = coverage::paths_not_hit(self.fun.paths.len(), samples.iter().map(|s| s.path));
 
             // Ensure y_min is always less than y_max
             if self.display_y_min > self.display_y_max {
@@ -270,9 +2088,76 @@ impl eframe::App for EllipticApp {
                 was_reset = true;
             }
 
+            let derivative_step = self.derivative_step().ok().flatten();
+            let round_trip = self.round_trip().ok().flatten().filter(|_| self.show_profit);
+            let num_subplots = [
+                self.show_gas,
+                show_reference,
+                bits_measure.is_some(),
+                self.other_fun.is_some(),
+                relation.is_some(),
+                derivative_step.is_some(),
+                round_trip.is_some(),
+            ].iter().filter(|&&shown| shown).count();
+            let subplot_height = ui.available_height() * (1.0 - MAIN_PLOT_FRACTION) / num_subplots.max(1) as f32;
+            if num_subplots > 0 {
+                plot = plot.height(ui.available_height() * MAIN_PLOT_FRACTION);
+            }
+
             let plot_response = plot.show(ui, |plot_ui| {
-                plot_ui.points(points);
-                plot_ui.points(errorPoints);
+                if self.color_by_path {
+                    for path_points in path_points(&samples, self.fun.paths) {
+                        plot_ui.points(path_points);
+                    }
+                } else {
+                    plot_ui.points(points);
+                }
+                plot_ui.points(error_points);
+                if !self.invariant_violations.is_empty() {
+                    let violations_vec: Vec<[f64; 2]> = self.invariant_violations.iter().map(|v| [v.x, v.y]).collect();
+                    plot_ui.points(Points::new("invariant violations", PlotPoints::new(violations_vec))
+                        .shape(egui_plot::MarkerShape::Cross)
+                        .color(Color32::from_rgb(255, 105, 180))
+                        .radius(6.0));
+                }
+                if !self.wrong_rounding.is_empty() {
+                    let wrong_vec: Vec<[f64; 2]> = self.wrong_rounding.iter().map(|w| [w.x, w.y]).collect();
+                    plot_ui.points(Points::new("wrong rounding direction", PlotPoints::new(wrong_vec))
+                        .shape(egui_plot::MarkerShape::Square)
+                        .color(Color32::from_rgb(230, 60, 0))
+                        .filled(false)
+                        .radius(4.0));
+                }
+                if !self.anomalies.is_empty() {
+                    let anomaly_vec: Vec<[f64; 2]> = self.anomalies.iter().map(|a| [a.x, a.y]).collect();
+                    plot_ui.points(Points::new("anomalies", PlotPoints::new(anomaly_vec))
+                        .shape(egui_plot::MarkerShape::Diamond)
+                        .color(Color32::GOLD)
+                        .filled(false)
+                        .radius(7.0));
+                }
+                for line in plateau_lines(&self.plateaus, self.fun) {
+                    plot_ui.line(line);
+                }
+                for line in violation_lines(&self.monotonicity_violations, self.fun) {
+                    plot_ui.line(line);
+                }
+                for boundary in &self.revert_boundaries {
+                    let xb = &self.fun.x_bounds;
+                    let x = u256_to_f64(boundary.after, xb.radix, xb.places);
+                    plot_ui.vline(egui_plot::VLine::new("revert boundary", x).color(Color32::RED).width(0.5));
+                }
+                if let Some(other) = self.other_fun {
+                    let (other_points, mismatch_points) = comparison_points(&samples, other);
+                    plot_ui.points(other_points);
+                    plot_ui.points(mismatch_points);
+                }
+                if show_reference {
+                    plot_ui.line(reference_line(&samples, &self.fun.y_bounds));
+                }
+                if self.show_round_trip && let Some(Relation::Inverse { inverse, .. }) = &relation {
+                    plot_ui.points(round_trip_points(&samples, inverse));
+                }
                 if was_reset {
                     let width = self.display_x_max - self.display_x_min;
                     let height = self.display_y_max - self.display_y_min;
@@ -295,73 +2180,334 @@ impl eframe::App for EllipticApp {
                 [max_pos.x, max_pos.y]
             );
             self.current_bounds = Some(bounds);
+
+            if self.show_gas {
+                subplot("gas_plot", "gas", subplot_height).show(ui, |plot_ui| {
+                    plot_ui.points(gas_points(&samples));
+                });
+            }
+            if show_reference {
+                let label = format!("{} error", self.error_unit.label());
+                subplot("error_plot", &label, subplot_height).show(ui, |plot_ui| {
+                    plot_ui.points(error_points_vs_reference(&samples, self.error_unit, &self.fun.y_bounds));
+                });
+            }
+            if let Some(measure) = bits_measure {
+                subplot("bits_plot", measure.label(), subplot_height).show(ui, |plot_ui| {
+                    plot_ui.points(bits_points(&samples, measure));
+                });
+            }
+            if let Some(other) = self.other_fun {
+                let label = format!("f(x) - {}(x), raw", other.name);
+                subplot("difference_plot", &label, subplot_height).show(ui, |plot_ui| {
+                    plot_ui.points(difference_points(&samples));
+                });
+            }
+            if let Some(step) = derivative_step {
+                subplot("derivative_plot", "dy/dx", subplot_height).show(ui, |plot_ui| {
+                    plot_ui.points(derivative_points(self.fun, &samples, step));
+                });
+            }
+            if let Some(round_trip) = &round_trip {
+                let label = format!("profit via {}, raw", round_trip.inverse.name);
+                subplot("profit_plot", &label, subplot_height).show(ui, |plot_ui| {
+                    let (loss_points, profit_points) = profit_points(round_trip, &samples);
+                    plot_ui.points(loss_points);
+                    plot_ui.points(profit_points);
+                });
+            }
+            if let Some(relation) = &relation {
+                let label = format!("{}, raw", relation.label());
+                subplot("relation_plot", &label, subplot_height).show(ui, |plot_ui| {
+                    let (deviation_points, violation_points) = deviation_points(&samples, relation);
+                    plot_ui.points(deviation_points);
+                    plot_ui.points(violation_points);
+                });
+            }
+
+            self.samples = samples;
         });
     }
 }
 
 
-/// Safely sample the curve with panic handling.
-/// Any x values for which the function reverts will be pushed into a second set of points called `errorPoints`
-/// These can be plotted in a different colour
-fn sample_curve_u256_safe(
-    num_points: usize,
-    x_min: f64,
-    x_max: f64,
-) -> (Points<'static>, Points<'static>) {
-    // Create a thread-safe counter to track which x value caused a panic
-    let current_x_index = Arc::new(AtomicUsize::new(0));
-
-    // Create a vector to store x values for each point
-    let x_values: Vec<f64> = (0..num_points)
-        .map(|i| {
-            let t = i as f64 / (num_points - 1) as f64;
-            x_min + t * (x_max - x_min)
+/// Splits the samples into the points that produced a value and the x values for which the function reverted.
+/// The latter are plotted in a different colour
+fn sample_points(samples: &[Sample]) -> (Points<'static>, Points<'static>) {
+    let points_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| s.y.map(|y| [s.x, y]))
+        .collect();
+    let error_points_vec: Vec<[f64; 2]> = samples.iter()
+        .filter(|s| s.y.is_none())
+        .map(|s| [s.x, 0.0])
+        .collect();
+
+    let mut points = Points::new("y = f(x)", PlotPoints::new(points_vec));
+    points = points.color(Color32::DARK_BLUE);
+    let mut error_points = Points::new("error points", PlotPoints::new(error_points_vec));
+    error_points = error_points.color(Color32::RED);
+    (points, error_points)
+}
+
+/// One series per code path, so each sample is coloured by the path it took and the legend names the paths.
+/// Samples whose function marked no path are grouped under "unmarked"
+fn path_points(samples: &[Sample], paths: &[&'static str]) -> Vec<Points<'static>> {
+    let mut by_path: Vec<Vec<[f64; 2]>> = vec![Vec::new(); paths.len() + 1];
+    for s in samples {
+        if let Some(y) = s.y {
+            let path = s.path.filter(|&p| p < paths.len()).unwrap_or(paths.len());
+            by_path[path].push([s.x, y]);
+        }
+    }
+
+    by_path.into_iter().enumerate()
+        .filter(|(_, points_vec)| !points_vec.is_empty())
+        .map(|(path, points_vec)| {
+            let (name, color) = match paths.get(path) {
+                Some(&name) => (name, PATH_COLORS[path % PATH_COLORS.len()]),
+                None => ("unmarked", Color32::GRAY),
+            };
+            Points::new(name, PlotPoints::new(points_vec)).color(color)
+        })
+        .collect()
+}
+
+/// A plot shown underneath the main one that shares its x axis
+fn subplot<'a>(id: &str, y_label: &str, height: f32) -> Plot<'a> {
+    Plot::new(id)
+        .link_axis("x_axis", [true, false])
+        .y_axis_label(y_label)
+        .height(height)
+        .legend(Legend::default())
+}
+
+/// The reference value at each sampled x, in display units
+fn reference_line(samples: &[Sample], y_bounds: &FixedPointBounds) -> Line<'static> {
+    let scale = Rational::scale(y_bounds.radix, y_bounds.places);
+    let reference_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| s.reference.as_ref().map(|r| [s.x, (r / &scale).to_f64()]))
+        .collect();
+    Line::new("reference", PlotPoints::new(reference_vec)).color(Color32::from_rgb(230, 140, 0))
+}
+
+/// Signed error of each successful evaluation against its reference
+fn error_points_vs_reference(samples: &[Sample], unit: ErrorUnit, y_bounds: &FixedPointBounds) -> Points<'static> {
+    let error_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| match (s.y_raw, &s.reference) {
+            (Some(y_raw), Some(r)) => Some([s.x, error_in(unit, y_raw, r, y_bounds)]),
+            _ => None,
         })
         .collect();
+    Points::new(format!("{} error", unit.label()), PlotPoints::new(error_vec)).color(Color32::DARK_RED)
+}
+
+/// Precision of each sample against its reference, in bits
+fn bits_points(samples: &[Sample], measure: BitsMeasure) -> Points<'static> {
+    let bits_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| Some([s.x, measure.bits(s.y_raw?, s.reference.as_ref()?)]))
+        .collect();
+    Points::new(measure.label(), PlotPoints::new(bits_vec)).color(Color32::DARK_BLUE)
+}
 
-    // Set up panic hook
-    let old_hook = panic::take_hook();
-    panic::set_hook(Box::new(|_| {})); // Silent hook
+/// The raw input with the worst precision and its bits
+fn worst_bits(samples: &[Sample], measure: BitsMeasure) -> Option<(U256, f64)> {
+    samples.iter()
+        .filter_map(|s| Some((s.x_raw, measure.bits(s.y_raw?, s.reference.as_ref()?))))
+        .reduce(|worst, b| if measure.is_worse(b.1, worst.1) { b } else { worst })
+}
 
-    // Separate points that produce a value from those that panic/revert
-    let mut points_vec = Vec::with_capacity(num_points);
+/// Whether the compared function disagrees with the plotted one at this sample.
+/// Reverting in one but not the other counts as a mismatch
+fn is_mismatch(sample: &Sample) -> bool {
+    sample.other_y_raw.is_some_and(|other| other != sample.y_raw)
+}
 
-    let mut error_points_vec = Vec::with_capacity(num_points);
+/// The compared function's values, and the samples at which the two functions disagree
+fn comparison_points(samples: &[Sample], other: &FixedPointFunction) -> (Points<'static>, Points<'static>) {
+    let yb = &other.y_bounds;
+    let other_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| s.other_y_raw.flatten().map(|y| [s.x, u256_to_f64(y, yb.radix, yb.places)]))
+        .collect();
+    let mismatch_vec: Vec<[f64; 2]> = samples.iter()
+        .filter(|s| is_mismatch(s))
+        .map(|s| [s.x, s.y.unwrap_or(0.0)])
+        .collect();
 
-    for (i, &x) in x_values.iter().enumerate() {
-        // Update the current index being processed
-        current_x_index.store(i, Ordering::SeqCst);
+    let other_points = Points::new(format!("{}(x)", other.name), PlotPoints::new(other_vec))
+        .color(Color32::LIGHT_BLUE);
+    let mismatch_points = Points::new("mismatch", PlotPoints::new(mismatch_vec))
+        .color(Color32::from_rgb(230, 140, 0))
+        .shape(egui_plot::MarkerShape::Circle)
+        .filled(false)
+        .radius(5.0);
+    (other_points, mismatch_points)
+}
 
-        if x.is_infinite() {
-            points_vec.push([x, 0.0]);
-            continue;
-        }
+/// `g(f(x))` for an inverse `g`, recovered from the round trip's deviation `g(f(x)) - x`
+fn round_trip_points(samples: &[Sample], inverse: &FixedPointFunction) -> Points<'static> {
+    let yb = &inverse.y_bounds;
+    let scale = Rational::scale(yb.radix, yb.places);
+    let round_trip_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| s.deviation.as_ref().map(|d| {
+            let round_trip = Rational::from_integer(rational::u256_to_bigint(s.x_raw) + d);
+            [s.x, (&round_trip / &scale).to_f64()]
+        }))
+        .collect();
+    Points::new(format!("{}(f(x))", inverse.name), PlotPoints::new(round_trip_vec))
+        .color(Color32::from_rgb(150, 60, 200))
+}
 
-        // Convert x_f64 -> U256
-        let (xb, yb) = (FIXED_POINT_FUN.x_bounds, FIXED_POINT_FUN.y_bounds);
-        let x_u256 = f64_to_u256(x, xb.radix, xb.places);
-        let result_y = panic::catch_unwind(AssertUnwindSafe(|| { (FIXED_POINT_FUN.fun)(x_u256) }));
-        match result_y {
-            Ok(y_u256) => {
-                let y = u256_to_f64(y_u256, yb.radix, yb.places);
-                points_vec.push([x, y]);
+/// Signed `f(x) - g(x)` in raw units wherever both functions produced a value
+fn difference_points(samples: &[Sample]) -> Points<'static> {
+    let difference_vec: Vec<[f64; 2]> = samples.iter()
+        .filter_map(|s| match (s.y_raw, s.other_y_raw.flatten()) {
+            (Some(y), Some(other)) => {
+                let difference = rational::u256_to_bigint(y) - rational::u256_to_bigint(other);
+                Some([s.x, difference.to_f64().unwrap_or(f64::NAN)])
             }
-            Err(_) => {
+            _ => None,
+        })
+        .collect();
+    Points::new("difference", PlotPoints::new(difference_vec)).color(Color32::DARK_RED)
+}
 
-                error_points_vec.push([x,0.0]);
-            }
+/// A horizontal segment spanning each plateau's exact inputs
+fn plateau_lines(plateaus: &[Plateau], fun: &FixedPointFunction) -> Vec<Line<'static>> {
+    let (xb, yb) = (&fun.x_bounds, &fun.y_bounds);
+    plateaus.iter()
+        .map(|p| {
+            let y = u256_to_f64(p.y, yb.radix, yb.places);
+            let (x0, x1) = (u256_to_f64(p.start, xb.radix, xb.places), u256_to_f64(p.end, xb.radix, xb.places));
+            Line::new("plateau", PlotPoints::new(vec![[x0, y], [x1, y]]))
+                .color(Color32::from_rgb(0, 160, 120))
+                .width(3.0)
+        })
+        .collect()
+}
+
+/// A segment joining the two samples of each monotonicity violation
+fn violation_lines(violations: &[monotonic::Violation], fun: &FixedPointFunction) -> Vec<Line<'static>> {
+    let (xb, yb) = (&fun.x_bounds, &fun.y_bounds);
+    let point = |x: U256, y: U256| [u256_to_f64(x, xb.radix, xb.places), u256_to_f64(y, yb.radix, yb.places)];
+    violations.iter()
+        .map(|v| {
+            Line::new("monotonicity violation", PlotPoints::new(vec![point(v.x0, v.y0), point(v.x1, v.y1)]))
+                .color(Color32::from_rgb(200, 0, 200))
+                .width(2.0)
+        })
+        .collect()
+}
+
+/// The relation's deviation at each sample, split into samples within and outside its tolerance
+fn deviation_points(samples: &[Sample], relation: &Relation) -> (Points<'static>, Points<'static>) {
+    let (mut ok_vec, mut violation_vec) = (Vec::new(), Vec::new());
+    for s in samples {
+        if let Some(d) = &s.deviation {
+            let point = [s.x, d.to_f64().unwrap_or(f64::NAN)];
+            if relation.is_violated(d) { violation_vec.push(point) } else { ok_vec.push(point) }
         }
     }
+    (
+        Points::new("deviation", PlotPoints::new(ok_vec)).color(Color32::DARK_BLUE),
+        Points::new("violation", PlotPoints::new(violation_vec)).color(Color32::RED),
+    )
+}
 
-    // Restore the original panic hook
-    panic::set_hook(old_hook);
+/// The forward difference at each sample, in display units
+fn derivative_points(fun: &FixedPointFunction, samples: &[Sample], step: Step) -> Points<'static> {
+    let derivative_vec: Vec<[f64; 2]> = derivative::derivative(fun, samples, step).iter()
+        .map(|(x, slope)| [*x, derivative::to_display_units(slope, fun)])
+        .collect();
+    Points::new("dy/dx", PlotPoints::new(derivative_vec)).color(Color32::DARK_GREEN)
+}
 
+/// A fixed plot for one of the distribution panel's histograms, with samples on the y axis
+fn histogram_plot<'a>(id: &str, x_label: &str) -> Plot<'a> {
+    Plot::new(id)
+        .height(HISTOGRAM_HEIGHT)
+        .x_axis_label(x_label)
+        .y_axis_label("samples")
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+}
 
-    let mut points = Points::new("y = f(x)", PlotPoints::new(points_vec));
-    points = points.color(Color32::DARK_BLUE);
-    let mut error_points = Points::new("error points", PlotPoints::new(error_points_vec));
-    error_points = error_points.color(Color32::RED);
-    (points, error_points)
+/// The histogram's counts as bars centred on each bin
+fn histogram_chart(name: &str, histogram: &Histogram, color: Color32) -> BarChart {
+    let bars = histogram.counts.iter().enumerate()
+        .map(|(i, &count)| Bar::new(histogram.bin_centre(i), count as f64).width(histogram.bin_width))
+        .collect();
+    BarChart::new(name, bars).color(color)
+}
+
+/// Plots output `a` of `fun` against output `b` as the input t runs over the samples, and reads out
+/// the sample nearest the pointer
+fn parametric_plot(ui: &mut egui::Ui, fun: &FixedPointFunctionMulti, samples: &[MultiSample], (a, b): (usize, usize)) {
+    let (ob, oa) = (&fun.outputs[b], &fun.outputs[a]);
+    let curve: Vec<(&MultiSample, [f64; 2])> = samples.iter()
+        .filter_map(|s| s.outputs.as_ref().ok().map(|ys| (s, [
+            u256_to_f64(ys[a], oa.bounds.radix, oa.bounds.places),
+            u256_to_f64(ys[b], ob.bounds.radix, ob.bounds.places),
+        ])))
+        .collect();
+    let points: Vec<[f64; 2]> = curve.iter().map(|&(_, p)| p).collect();
+
+    let hovered = Plot::new("parametric_plot")
+        .legend(Legend::default())
+        .x_axis_label(oa.name)
+        .y_axis_label(ob.name)
+        .default_x_bounds(oa.bounds.min, oa.bounds.max)
+        .default_y_bounds(ob.bounds.min, ob.bounds.max)
+        .height(ui.available_height() - 2.0 * ui.spacing().interact_size.y)
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(format!("({}, {})", oa.name, ob.name), points.clone()).color(Color32::DARK_BLUE));
+            plot_ui.points(Points::new("samples", points.clone()).color(Color32::DARK_BLUE).radius(2.0));
+            if let Some(&start) = points.first() {
+                plot_ui.points(Points::new("start of t", vec![start]).color(PATH_COLORS[1]).radius(5.0));
+            }
+            // Nearest sample in screen space, so both axes count alike whatever their scales
+            let pointer = plot_ui.pointer_coordinate()?;
+            let bounds = plot_ui.plot_bounds();
+            let (w, h) = (bounds.width(), bounds.height());
+            curve.iter()
+                .map(|&(s, [x, y])| (s, ((x - pointer.x) / w).powi(2) + ((y - pointer.y) / h).powi(2)))
+                .min_by(|p, q| p.1.total_cmp(&q.1))
+                .map(|(s, _)| s)
+        })
+        .inner;
+
+    ui.label(match hovered.and_then(|s| Some((s, s.outputs.as_ref().ok()?))) {
+        Some((s, ys)) => format!("t = {} ({}): {} = {}, {} = {}", s.x_raw, s.x, oa.name, ys[a], ob.name, ys[b]),
+        None => "Hover over the curve to read the sample nearest the pointer".to_string(),
+    });
+}
+
+/// The round trip's profit at each sample, split into losses (or break-even) and profits
+fn profit_points(round_trip: &RoundTrip, samples: &[Sample]) -> (Points<'static>, Points<'static>) {
+    let (mut loss_vec, mut profit_vec) = (Vec::new(), Vec::new());
+    for (x, profit) in profit::profit_series(round_trip, samples) {
+        let point = [x, profit.to_f64().unwrap_or(f64::NAN)];
+        if profit > num_bigint::BigInt::ZERO { profit_vec.push(point) } else { loss_vec.push(point) }
+    }
+    (
+        Points::new("no profit", PlotPoints::new(loss_vec)).color(Color32::DARK_BLUE),
+        Points::new("profit", PlotPoints::new(profit_vec)).color(Color32::RED),
+    )
+}
+
+/// Estimated gas used by each evaluation, including the ones that reverted
+fn gas_points(samples: &[Sample]) -> Points<'static> {
+    let gas_vec: Vec<[f64; 2]> = samples.iter()
+        .map(|s| [s.x, s.gas as f64])
+        .collect();
+    Points::new("gas", PlotPoints::new(gas_vec)).color(Color32::DARK_GREEN)
+}
+
+/// Colour of a result `t` of the way from the lowest to the highest in a heatmap
+fn heat_color(t: f64) -> Color32 {
+    let scaled = t.clamp(0.0, 1.0) * (HEATMAP_COLORS.len() - 1) as f64;
+    let i = (scaled.floor() as usize).min(HEATMAP_COLORS.len() - 2);
+    HEATMAP_COLORS[i].lerp_to_gamma(HEATMAP_COLORS[i + 1], (scaled - i as f64) as f32)
 }
 
 fn main() -> Result<(), eframe::Error> {
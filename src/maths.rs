@@ -1,6 +1,8 @@
 use num_bigint::BigUint;
 use num_traits::ToPrimitive; // give us `to_f64`
 use primitive_types::{U256};
+use crate::params::Parameter;
+use crate::rational::Rational;
 
 pub struct FixedPointNumPoints {
     pub default: usize,
@@ -15,12 +17,139 @@ pub struct FixedPointBounds {
     pub max: f64
 }
 
+impl FixedPointBounds {
+    /// Whether both bounds read and write numbers in the same fixed-point format
+    pub fn same_format(&self, other: &FixedPointBounds) -> bool {
+        self.radix == other.radix && self.places == other.places
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Monotonicity {
+    pub direction: Direction,
+    pub strict: bool,
+}
+
+impl Monotonicity {
+    /// Whether `y0 = f(x0)` and `y1 = f(x1)` respect the monotonicity, for `x0 < x1`
+    pub fn holds(&self, y0: U256, y1: U256) -> bool {
+        match (self.direction, self.strict) {
+            (Direction::Increasing, false) => y0 <= y1,
+            (Direction::Increasing, true) => y0 < y1,
+            (Direction::Decreasing, false) => y0 >= y1,
+            (Direction::Decreasing, true) => y0 > y1,
+        }
+    }
+}
+
+/// The direction a result should be rounded in, i.e. the one that favours the protocol
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+impl Rounding {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Rounding::Down => "down",
+            Rounding::Up => "up",
+        }
+    }
+}
+
 pub struct FixedPointFunction {
     pub name: &'static str,
     pub fun: fn(U256) -> U256,
     pub x_bounds: FixedPointBounds,
     pub y_bounds: FixedPointBounds,
     pub num_points: FixedPointNumPoints,
+    /* Names of the code paths the function marks with `coverage::mark`, indexed by path ID */
+    pub paths: &'static [&'static str],
+    /* Ideal value of the function in raw output units, computed exactly or to high precision */
+    pub reference: Option<fn(U256) -> Rational>,
+    /* How the output is expected to change as the input grows */
+    pub monotonicity: Option<Monotonicity>,
+    /* Direction the output should round in against the reference, in favour of the protocol */
+    pub rounding: Option<Rounding>,
+    /* Conditions expected to hold at every input, in the expression language of `expr.rs` */
+    pub invariants: &'static [&'static str],
+    /* Parameters the function reads with `params::get` */
+    pub params: &'static [Parameter],
+}
+
+impl FixedPointFunction {
+    /// Whether the two functions read their input and write their output in the same fixed-point
+    /// format, so their raw results can be compared at the same raw input
+    pub fn is_comparable_with(&self, other: &FixedPointFunction) -> bool {
+        self.x_bounds.same_format(&other.x_bounds) && self.y_bounds.same_format(&other.y_bounds)
+    }
+
+    /// Whether the output can be fed back in as the input, so the function can be iterated
+    pub fn is_iterable(&self) -> bool {
+        self.is_invertible_by(self)
+    }
+
+    /// Whether `other` reads the format this function writes and writes the format it reads,
+    /// so `other(self(x))` can be compared with `x`
+    pub fn is_invertible_by(&self, other: &FixedPointFunction) -> bool {
+        self.y_bounds.same_format(&other.x_bounds) && self.x_bounds.same_format(&other.y_bounds)
+    }
+}
+
+/// A function of two inputs, sampled over a grid and drawn as a heatmap
+pub struct FixedPointFunction2 {
+    pub name: &'static str,
+    pub fun: fn(U256, U256) -> U256,
+    /* Bounds of the first and second input, drawn along the plot's horizontal and vertical axes */
+    pub x_bounds: FixedPointBounds,
+    pub y_bounds: FixedPointBounds,
+    /* Bounds of the output, drawn as the colour of each cell */
+    pub z_bounds: FixedPointBounds,
+    /* Cells along each axis */
+    pub num_points: FixedPointNumPoints,
+    pub params: &'static [Parameter],
+}
+
+/// An input of a `FixedPointFunctionN`. The inputs that aren't plotted are read as parameters
+pub struct FixedPointInput {
+    pub param: Parameter,
+    /* Bounds of the input when it is plotted along x, and of its slider otherwise */
+    pub bounds: FixedPointBounds,
+}
+
+/// A function of several inputs, plotted one slice at a time: one input along x with the others
+/// held at the values of their sliders, see `slicing.rs`
+pub struct FixedPointFunctionN {
+    pub name: &'static str,
+    pub fun: fn(&[U256]) -> U256,
+    pub inputs: &'static [FixedPointInput],
+    pub y_bounds: FixedPointBounds,
+    pub num_points: FixedPointNumPoints,
+    pub paths: &'static [&'static str],
+}
+
+/// A named output of a `FixedPointFunctionMulti`
+pub struct FixedPointOutput {
+    pub name: &'static str,
+    pub bounds: FixedPointBounds,
+}
+
+/// A function returning several outputs, e.g. an amount and a fee, plotted as one series per output
+pub struct FixedPointFunctionMulti {
+    pub name: &'static str,
+    /* Returns one value per entry of `outputs`, in the same order */
+    pub fun: fn(U256) -> Vec<U256>,
+    pub x_bounds: FixedPointBounds,
+    pub outputs: &'static [FixedPointOutput],
+    pub num_points: FixedPointNumPoints,
+    pub params: &'static [Parameter],
 }
 
 // Converts a U256 fixed-point number to f64 with `decimals` fractional digits
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use primitive_types::{U256};
+
+use crate::functions;
+use crate::maths::u256d;
+
+/*
+ * Parameters of the plotted functions, e.g. a pool's amplification or the balances it holds besides
+ * the plotted one.
+ *
+ * A function declares its parameters in `FixedPointFunction::params` and reads them with `get`, which
+ * returns the value currently set under the parameter's name, or its default. Like the gas meter and
+ * the coverage recorder the values are thread-local, so every analysis that evaluates a function sees
+ * the same parameters as the plot, and functions declaring a parameter of the same name (e.g. a
+ * function and its inverse over the same pool) share its value.
+ */
+
+#[derive(Clone, Copy)]
+pub struct Parameter {
+    /// An identifier, so expressions can refer to the parameter
+    pub name: &'static str,
+    /// Raw values, in decimal
+    pub default: &'static str,
+    pub min: &'static str,
+    pub max: &'static str,
+}
+
+impl Parameter {
+    pub fn default_value(&self) -> U256 {
+        u256d(self.default)
+    }
+
+    pub fn range(&self) -> (U256, U256) {
+        (u256d(self.min), u256d(self.max))
+    }
+}
+
+thread_local! {
+    static VALUES: RefCell<BTreeMap<&'static str, U256>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+pub fn get(param: &Parameter) -> U256 {
+    value(param.name).unwrap_or_else(|| param.default_value())
+}
+
+/// The value set under `name`, if any
+fn value(name: &str) -> Option<U256> {
+    VALUES.with(|v| v.borrow().get(name).copied())
+}
+
+/// The value set under `name`, or the default of the first registered function's parameter of that
+/// name, for expressions that refer to parameters by name
+pub fn get_by_name(name: &str) -> Option<U256> {
+    value(name).or_else(|| {
+        functions::ALL.iter()
+            .flat_map(|fun| fun.params)
+            .find(|p| p.name == name)
+            .map(Parameter::default_value)
+    })
+}
+
+pub fn set(name: &'static str, value: U256) {
+    VALUES.with(|v| v.borrow_mut().insert(name, value));
+}
+
+pub fn clear(name: &str) {
+    VALUES.with(|v| v.borrow_mut().remove(name));
+}
+
+/// Runs `f` with `params` set to `values`, restoring the previous values afterwards
+pub fn with_values<R>(params: &[Parameter], values: &[U256], f: impl FnOnce() -> R) -> R {
+    let previous: Vec<Option<U256>> = params.iter().map(|p| value(p.name)).collect();
+    for (p, &value) in params.iter().zip(values) {
+        set(p.name, value);
+    }
+    let result = f();
+    for (p, prev) in params.iter().zip(previous) {
+        match prev {
+            Some(value) => set(p.name, value),
+            None => clear(p.name),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_values() -> Result<(), Box<dyn std::error::Error>> {
+        const PARAMS: &[Parameter] = &[Parameter { name: "test_param", default: "7", min: "0", max: "10" }];
+        assert_eq!(get(&PARAMS[0]), u256d("7"));
+        let inside = with_values(PARAMS, &[u256d("3")], || get(&PARAMS[0]));
+        assert_eq!(inside, u256d("3"));
+        assert_eq!(get(&PARAMS[0]), u256d("7"));
+
+        // An unset parameter takes its own default, even when another of the same name has a different one
+        let other = Parameter { name: "test_param", default: "9", ..PARAMS[0] };
+        assert_eq!(get(&other), u256d("9"));
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{Signed, ToPrimitive, Zero};
+use primitive_types::{U256};
+
+/*
+ * Exact rational number, used for ideal reference values that fixed-point results are compared
+ * against. The denominator is always positive. Fractions are not reduced, which is fine for the
+ * short chains of operations references are made of.
+ */
+#[derive(Clone, Debug)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+impl Rational {
+    pub fn new(num: BigInt, den: BigInt) -> Self {
+        assert!(!den.is_zero(), "zero denominator");
+        if den.is_negative() { Rational { num: -num, den: -den } } else { Rational { num, den } }
+    }
+
+    pub fn from_integer(num: BigInt) -> Self {
+        Rational { num, den: BigInt::from(1) }
+    }
+
+    pub fn from_u256(value: U256) -> Self {
+        Rational::from_integer(u256_to_bigint(value))
+    }
+
+    /// `radix^places`, the factor between a fixed-point number's raw and real value
+    pub fn scale(radix: u8, places: u32) -> Self {
+        Rational::from_integer(BigInt::from(radix).pow(places))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.num.is_negative()
+    }
+
+    pub fn abs(&self) -> Self {
+        Rational { num: self.num.abs(), den: self.den.clone() }
+    }
+
+    /// Largest integer not greater than the value
+    pub fn floor(&self) -> BigInt {
+        let q = &self.num / &self.den;
+        if self.num.is_negative() && &q * &self.den != self.num { q - 1 } else { q }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        // Shift both sides down so they fit in an f64 without overflowing to infinity
+        let excess = self.num.bits().max(self.den.bits()).saturating_sub(1000);
+        let num = (&self.num >> excess).to_f64().unwrap_or(f64::NAN);
+        let den = (&self.den >> excess).to_f64().unwrap_or(f64::NAN);
+        if den == 0.0 { f64::NAN } else { num / den }
+    }
+}
+
+pub fn u256_to_bigint(value: U256) -> BigInt {
+    BigInt::from(BigUint::from_bytes_be(&value.to_big_endian()))
+}
+
+/// `None` if the value is negative or doesn't fit in 256 bits
+pub fn bigint_to_u256(value: &BigInt) -> Option<U256> {
+    let (sign, bytes) = value.to_bytes_be();
+    if sign == num_bigint::Sign::Minus || bytes.len() > 32 { None } else { Some(U256::from_big_endian(&bytes)) }
+}
+
+impl PartialEq for Rational {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Rational {}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.num * &other.den).cmp(&(&other.num * &self.den))
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+    fn add(self, other: &Rational) -> Rational {
+        Rational::new(&self.num * &other.den + &other.num * &self.den, &self.den * &other.den)
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+    fn sub(self, other: &Rational) -> Rational {
+        Rational::new(&self.num * &other.den - &other.num * &self.den, &self.den * &other.den)
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+    fn mul(self, other: &Rational) -> Rational {
+        Rational::new(&self.num * &other.num, &self.den * &other.den)
+    }
+}
+
+impl Div for &Rational {
+    type Output = Rational;
+    fn div(self, other: &Rational) -> Rational {
+        Rational::new(&self.num * &other.den, &self.den * &other.num)
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        Rational { num: -&self.num, den: self.den.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rational() -> Result<(), Box<dyn std::error::Error>> {
+        let third = Rational::new(BigInt::from(1), BigInt::from(3));
+        let half = Rational::new(BigInt::from(-1), BigInt::from(-2));
+        assert_eq!(&(&third + &third) + &third, Rational::from_integer(BigInt::from(1)));
+        assert!(third < half);
+        assert_eq!((&third - &half).floor(), BigInt::from(-1));
+        assert_eq!((&half / &third).floor(), BigInt::from(1));
+        assert_eq!((&half * &third).to_f64(), 1.0 / 6.0);
+        Ok(())
+    }
+}
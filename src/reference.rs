@@ -0,0 +1,112 @@
+use primitive_types::{U256};
+
+use crate::maths::*;
+use crate::rational::Rational;
+
+/*
+ * Error of a fixed-point result against the function's ideal reference value.
+ * References are expressed in raw output units, the same units as the fixed-point result.
+ */
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorUnit {
+    /// Difference in the output's real (display) units
+    Absolute,
+    /// Difference divided by the reference value
+    Relative,
+    /// Difference in units in the last place, i.e. raw units of the fixed-point output
+    Ulp,
+}
+
+impl ErrorUnit {
+    pub const ALL: [ErrorUnit; 3] = [ErrorUnit::Absolute, ErrorUnit::Relative, ErrorUnit::Ulp];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorUnit::Absolute => "absolute",
+            ErrorUnit::Relative => "relative",
+            ErrorUnit::Ulp => "ULP",
+        }
+    }
+}
+
+/// How a sample's precision is counted in bits
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BitsMeasure {
+    /// `log2(|y - reference| + 1)` in raw units, the low bits of the result that are wrong
+    Lost,
+    /// The bits of the reference's magnitude that survive, `log2(|reference| + 1)` minus the bits lost
+    Correct,
+}
+
+impl BitsMeasure {
+    pub const ALL: [BitsMeasure; 2] = [BitsMeasure::Lost, BitsMeasure::Correct];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BitsMeasure::Lost => "bits lost",
+            BitsMeasure::Correct => "correct bits",
+        }
+    }
+
+    pub fn bits(&self, y_raw: U256, reference: &Rational) -> f64 {
+        let one = Rational::from_u256(U256::one());
+        let log2 = |r: Rational| (&r.abs() + &one).to_f64().log2();
+        let lost = log2(raw_error(y_raw, reference));
+        match self {
+            BitsMeasure::Lost => lost,
+            BitsMeasure::Correct => (log2(reference.clone()) - lost).max(0.0),
+        }
+    }
+
+    /// Whether `a` is a worse precision than `b`
+    pub fn is_worse(&self, a: f64, b: f64) -> bool {
+        match self {
+            BitsMeasure::Lost => a > b,
+            BitsMeasure::Correct => a < b,
+        }
+    }
+}
+
+/// Signed `y - reference` in raw output units
+pub fn raw_error(y_raw: U256, reference: &Rational) -> Rational {
+    &Rational::from_u256(y_raw) - reference
+}
+
+/// Signed error of `y_raw` against `reference` in the given unit, exactly.
+/// `None` for the relative error of a non-zero result against a zero reference
+pub fn exact_error(unit: ErrorUnit, y_raw: U256, reference: &Rational, y_bounds: &FixedPointBounds) -> Option<Rational> {
+    let err = raw_error(y_raw, reference);
+    match unit {
+        ErrorUnit::Absolute => Some(&err / &Rational::scale(y_bounds.radix, y_bounds.places)),
+        ErrorUnit::Relative if reference.is_zero() => if err.is_zero() { Some(err) } else { None },
+        ErrorUnit::Relative => Some(&err / reference),
+        ErrorUnit::Ulp => Some(err),
+    }
+}
+
+/// Signed error of `y_raw` against `reference` in the given unit.
+/// The relative error of a non-zero result against a zero reference is infinite
+pub fn error_in(unit: ErrorUnit, y_raw: U256, reference: &Rational, y_bounds: &FixedPointBounds) -> f64 {
+    exact_error(unit, y_raw, reference, y_bounds).map_or(f64::INFINITY, |err| err.to_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+
+    #[test]
+    fn test_bits() -> Result<(), Box<dyn std::error::Error>> {
+        let reference = Rational::from_u256(u256d("1023"));
+        assert_eq!(BitsMeasure::Lost.bits(u256d("1023"), &reference), 0.0);
+        assert_eq!(BitsMeasure::Lost.bits(u256d("1030"), &reference), 3.0);
+        assert_eq!(BitsMeasure::Correct.bits(u256d("1023"), &reference), 10.0);
+        assert_eq!(BitsMeasure::Correct.bits(u256d("1030"), &reference), 7.0);
+        assert_eq!(BitsMeasure::Correct.bits(u256d("0"), &reference), 0.0);
+
+        let half = Rational::new(BigInt::from(1), BigInt::from(2));
+        assert_eq!(BitsMeasure::Lost.bits(u256d("0"), &half), 1.5_f64.log2());
+        Ok(())
+    }
+}
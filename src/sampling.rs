@@ -0,0 +1,267 @@
+use num_bigint::BigInt;
+use primitive_types::{U256};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::analysis::metamorphic::Relation;
+use crate::coverage;
+use crate::gas;
+use crate::maths::*;
+use crate::params;
+use crate::rational::{u256_to_bigint, Rational};
+
+/// One evaluation of the plotted function.
+/// `y` is `None` when the function reverted (panicked) at `x_raw`.
+pub struct Sample {
+    pub x: f64,
+    pub x_raw: U256,
+    pub y: Option<f64>,
+    pub y_raw: Option<U256>,
+    /* The function's reference value at `x_raw`, when requested and available */
+    pub reference: Option<Rational>,
+    /* Result of the function being compared against at the same raw input: `None` if none is
+     * being compared, `Some(None)` if it reverted */
+    pub other_y_raw: Option<Option<U256>>,
+    /* Deviation of the metamorphic relation being checked, in raw output units */
+    pub deviation: Option<BigInt>,
+    pub gas: u64,
+    /* The panic message when the function reverted */
+    pub revert: Option<String>,
+    /* Index into `FixedPointFunction::paths` of the last path the function marked */
+    pub path: Option<usize>,
+}
+
+impl Sample {
+    /// Value of a variable for expressions evaluated at this sample: `x` and `y` in raw units,
+    /// `ref` (the floor of the reference) and `g` (the compared function's result) when available,
+    /// and any parameter set by name
+    pub fn var(&self, name: &str) -> Option<BigInt> {
+        match name {
+            "x" => Some(u256_to_bigint(self.x_raw)),
+            "y" => self.y_raw.map(u256_to_bigint),
+            "ref" => self.reference.as_ref().map(|r| r.floor()),
+            "g" => self.other_y_raw.flatten().map(u256_to_bigint),
+            _ => params::get_by_name(name).map(u256_to_bigint),
+        }
+    }
+}
+
+/// What to compute alongside each evaluation
+#[derive(Default)]
+pub struct SampleOptions<'a> {
+    pub reference: bool,
+    /* A second function evaluated at the same raw inputs */
+    pub compare_with: Option<&'a FixedPointFunction>,
+    /* A metamorphic relation, evaluated at inputs derived from each sample */
+    pub relation: Option<&'a Relation>,
+}
+
+/// Runs `f` with the panic hook silenced, so reverting functions don't print to stderr
+pub fn with_silent_panics<R>(f: impl FnOnce() -> R) -> R {
+    let old_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {})); // Silent hook
+    let result = f();
+    // Restore the original panic hook
+    panic::set_hook(old_hook);
+    result
+}
+
+/// Evaluates `fun` at `x`, returning `None` if it reverts.
+/// The caller is expected to have silenced the panic hook, see `with_silent_panics`
+pub fn eval(fun: &FixedPointFunction, x: U256) -> Option<U256> {
+    eval_or_revert(fun, x).ok()
+}
+
+/// Evaluates `fun` at `x`, returning the panic message if it reverts.
+/// The caller is expected to have silenced the panic hook, see `with_silent_panics`
+pub fn eval_or_revert(fun: &FixedPointFunction, x: U256) -> Result<U256, String> {
+    catch_revert(|| (fun.fun)(x))
+}
+
+/// Runs `f`, returning the panic message if it panics.
+/// The caller is expected to have silenced the panic hook, see `with_silent_panics`
+pub fn catch_revert<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+            (Some(message), _) => message.to_string(),
+            (_, Some(message)) => message.clone(),
+            _ => "unknown".to_string(),
+        }
+    })
+}
+
+/// Safely sample the curve with panic handling.
+/// Any x values for which the function reverts are returned with `y == None`
+/// so they can be plotted in a different colour
+pub fn sample_curve_u256_safe(
+    fun: &FixedPointFunction,
+    options: &SampleOptions<'_>,
+    num_points: usize,
+    x_min: f64,
+    x_max: f64,
+) -> Vec<Sample> {
+    // Create a thread-safe counter to track which x value caused a panic
+    let current_x_index = Arc::new(AtomicUsize::new(0));
+
+    // Create a vector to store x values for each point
+    let x_values: Vec<f64> = (0..num_points)
+        .map(|i| {
+            let t = i as f64 / (num_points - 1) as f64;
+            x_min + t * (x_max - x_min)
+        })
+        .collect();
+
+    // Set up panic hook
+    let old_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {})); // Silent hook
+
+    let mut samples = Vec::with_capacity(num_points);
+
+    for (i, &x) in x_values.iter().enumerate() {
+        // Update the current index being processed
+        current_x_index.store(i, Ordering::SeqCst);
+
+        if x.is_infinite() {
+            samples.push(Sample { x, x_raw: U256::zero(), y: Some(0.0), y_raw: None, reference: None, other_y_raw: None, deviation: None, gas: 0, revert: None, path: None });
+            continue;
+        }
+
+        // Convert x_f64 -> U256
+        let (xb, yb) = (&fun.x_bounds, &fun.y_bounds);
+        let x_u256 = f64_to_u256(x, xb.radix, xb.places);
+        gas::reset();
+        coverage::reset();
+        let result = eval_or_revert(fun, x_u256);
+        let result_y = result.as_ref().ok().copied();
+        let (gas, path) = (gas::gas_used(), coverage::path_taken());
+        let reference = fun.reference.filter(|_| options.reference).map(|r| r(x_u256));
+        let other_y_raw = options.compare_with.map(|other| eval(other, x_u256));
+        let deviation = match (options.relation, result_y) {
+            (Some(relation), Some(y_u256)) => relation.deviation(fun, x_u256, y_u256),
+            _ => None,
+        };
+        match result {
+            Ok(y_u256) => {
+                let y = u256_to_f64(y_u256, yb.radix, yb.places);
+                samples.push(Sample { x, x_raw: x_u256, y: Some(y), y_raw: Some(y_u256), reference, other_y_raw, deviation, gas, revert: None, path });
+            }
+            Err(message) => {
+                samples.push(Sample { x, x_raw: x_u256, y: None, y_raw: None, reference, other_y_raw, deviation, gas, revert: Some(message), path });
+            }
+        }
+    }
+
+    // Restore the original panic hook
+    panic::set_hook(old_hook);
+
+    samples
+}
+
+/// One evaluation of a function with several outputs
+pub struct MultiSample {
+    pub x: f64,
+    pub x_raw: U256,
+    /* Every output in raw units, or the panic message when the function reverted */
+    pub outputs: Result<Vec<U256>, String>,
+}
+
+/// Evaluates `fun` at `num_points` evenly spaced inputs from `x_min` to `x_max`
+pub fn sample_outputs(fun: &FixedPointFunctionMulti, num_points: usize, x_min: f64, x_max: f64) -> Vec<MultiSample> {
+    let xb = &fun.x_bounds;
+    with_silent_panics(|| {
+        (0..num_points)
+            .map(|i| {
+                let x = x_min + i as f64 / (num_points - 1) as f64 * (x_max - x_min);
+                let x_raw = f64_to_u256(x, xb.radix, xb.places);
+                MultiSample { x, x_raw, outputs: catch_revert(|| (fun.fun)(x_raw)) }
+            })
+            .collect()
+    })
+}
+
+/// A two-input function's results over a grid of cells, stored row by row from the lowest second input
+pub struct Grid {
+    /* Inputs at the centre of each column and row, in display units */
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub xs_raw: Vec<U256>,
+    pub ys_raw: Vec<U256>,
+    /* The result or the panic message of each cell, at index `row * xs.len() + column` */
+    pub values: Vec<Result<U256, String>>,
+}
+
+impl Grid {
+    pub fn get(&self, column: usize, row: usize) -> &Result<U256, String> {
+        &self.values[row * self.xs.len() + column]
+    }
+
+    /// Smallest and largest result of the cells that didn't revert
+    pub fn range(&self) -> Option<(U256, U256)> {
+        let mut results = self.values.iter().filter_map(|v| v.as_ref().ok());
+        let first = *results.next()?;
+        Some(results.fold((first, first), |(lo, hi), &v| (lo.min(v), hi.max(v))))
+    }
+}
+
+/// Evaluates `fun` at the centre of each of `num_x` by `num_y` cells covering the ranges
+pub fn sample_grid(
+    fun: &FixedPointFunction2,
+    (num_x, num_y): (usize, usize),
+    (x_min, x_max): (f64, f64),
+    (y_min, y_max): (f64, f64),
+) -> Grid {
+    let centres = |n: usize, min: f64, max: f64| -> Vec<f64> {
+        (0..n).map(|i| min + (i as f64 + 0.5) / n as f64 * (max - min)).collect()
+    };
+    let (xs, ys) = (centres(num_x, x_min, x_max), centres(num_y, y_min, y_max));
+    let (xb, yb) = (&fun.x_bounds, &fun.y_bounds);
+    let xs_raw: Vec<U256> = xs.iter().map(|&x| f64_to_u256(x, xb.radix, xb.places)).collect();
+    let ys_raw: Vec<U256> = ys.iter().map(|&y| f64_to_u256(y, yb.radix, yb.places)).collect();
+
+    let values = with_silent_panics(|| {
+        ys_raw.iter()
+            .flat_map(|&y| xs_raw.iter().map(move |&x| (x, y)))
+            .map(|(x, y)| catch_revert(|| (fun.fun)(x, y)))
+            .collect()
+    });
+    Grid { xs, ys, xs_raw, ys_raw, values }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::curve::{curve_get_D_balances, curve_get_dy};
+
+    #[test]
+    fn test_sample_grid() -> Result<(), Box<dyn std::error::Error>> {
+        let grid = sample_grid(&curve_get_D_balances, (4, 3), (0.0, 8.0), (0.0, 6.0));
+        assert_eq!(grid.xs, vec![1.0, 3.0, 5.0, 7.0]);
+        assert_eq!(grid.ys, vec![1.0, 3.0, 5.0]);
+        assert_eq!(grid.values.len(), 12);
+
+        // The invariant only depends on the set of balances
+        let (a, b) = (grid.get(1, 0).as_ref().unwrap(), grid.get(0, 1).as_ref().unwrap());
+        assert_eq!(a, b);
+        // A balanced pool's invariant is the sum of its balances
+        assert_eq!(*grid.get(1, 1).as_ref().unwrap(), u256d("6000000000000000000"));
+
+        let (lo, hi) = grid.range().unwrap();
+        assert_eq!(lo, u256d("2000000000000000000"));
+        assert!(hi > u256d("11000000000000000000") && hi <= u256d("12000000000000000000"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_outputs() -> Result<(), Box<dyn std::error::Error>> {
+        let samples = sample_outputs(&curve_get_dy, 5, 0.0, 2.0);
+        assert_eq!(samples.iter().map(|s| s.x).collect::<Vec<_>>(), vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+        // However much is traded in, less than the pool's balance comes out
+        for s in &samples[1..] {
+            let outputs = s.outputs.as_ref().unwrap();
+            assert_eq!(outputs.len(), 2);
+            assert!(outputs[0] + outputs[1] < s.x_raw.min(u256d("1000000000000000000")));
+        }
+        Ok(())
+    }
+}
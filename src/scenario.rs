@@ -0,0 +1,81 @@
+use primitive_types::{U256};
+
+use crate::sampling::{catch_revert, with_silent_panics};
+
+pub mod curve_pool;
+
+/*
+ * Stateful scenarios: a sequence of calls, each mapping the state left by the previous one and an
+ * amount to a new state. One step's amount is varied over a range while the others stay fixed, so
+ * any state variable can be plotted against it after each step. Operations revert by panicking,
+ * the same as plotted functions.
+ */
+
+pub struct Operation<S> {
+    pub name: &'static str,
+    pub apply: fn(&S, U256) -> S,
+}
+
+pub struct Variable<S> {
+    pub name: &'static str,
+    pub get: fn(&S) -> U256,
+}
+
+pub struct Scenario<S: 'static> {
+    pub name: &'static str,
+    pub initial: fn() -> S,
+    pub operations: &'static [Operation<S>],
+    pub variables: &'static [Variable<S>],
+    /* Decimal places of the amounts and variables, for display */
+    pub places: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Step {
+    /// Index into `Scenario::operations`
+    pub operation: usize,
+    pub amount: U256,
+}
+
+/// The states after each step, up to the step that reverted if any
+pub struct Run<S> {
+    pub states: Vec<S>,
+    /// Every variable of the scenario read after each step, or the panic message if reading it reverted
+    pub values: Vec<Vec<Result<U256, String>>>,
+    /// Index of the step that reverted and its panic message
+    pub revert: Option<(usize, String)>,
+}
+
+/// Applies the steps in order from the scenario's initial state.
+/// The caller is expected to have silenced the panic hook
+pub fn run<S>(scenario: &Scenario<S>, steps: &[Step]) -> Run<S> {
+    let mut states: Vec<S> = Vec::with_capacity(steps.len());
+    let mut values = Vec::with_capacity(steps.len());
+    let initial = (scenario.initial)();
+    for (i, step) in steps.iter().enumerate() {
+        let state = states.last().unwrap_or(&initial);
+        let apply = scenario.operations[step.operation].apply;
+        match catch_revert(|| apply(state, step.amount)) {
+            Ok(next) => {
+                // Variables can revert on states no step checks, e.g. `D` of a pool too unbalanced to converge
+                values.push(scenario.variables.iter().map(|v| catch_revert(|| (v.get)(&next))).collect());
+                states.push(next);
+            }
+            Err(message) => return Run { states, values, revert: Some((i, message)) },
+        }
+    }
+    Run { states, values, revert: None }
+}
+
+/// Runs the steps once per amount, with the amount of step `varied` replaced
+pub fn sweep<S>(scenario: &Scenario<S>, steps: &[Step], varied: usize, amounts: &[U256]) -> Vec<Run<S>> {
+    let mut steps = steps.to_vec();
+    with_silent_panics(|| {
+        amounts.iter()
+            .map(|&amount| {
+                steps[varied].amount = amount;
+                run(scenario, &steps)
+            })
+            .collect()
+    })
+}
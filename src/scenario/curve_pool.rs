@@ -0,0 +1,136 @@
+use primitive_types::{U256};
+
+use crate::functions::curve::{get_D, get_y_D, AMP};
+use crate::maths::*;
+use crate::params;
+use crate::scenario::{Operation, Scenario, Variable};
+use crate::vyper::*;
+
+/*
+ * A two-coin StableSwap pool with the same `amp` parameter as the plotted `curve_get_D`, without fees.
+ * Amounts are in raw units of coins with 18 decimals, and LP tokens for the liquidity operations.
+ */
+
+#[derive(Clone, Debug)]
+pub struct PoolState {
+    pub balances: [U256; 2],
+    /// Total supply of LP tokens
+    pub supply: U256,
+}
+
+pub const curve_pool: Scenario<PoolState> = Scenario {
+    name: "curve_pool",
+    initial,
+    operations: &[
+        Operation { name: "add_liquidity coin 0", apply: |s, amount| add_liquidity(s, 0, amount) },
+        Operation { name: "add_liquidity coin 1", apply: |s, amount| add_liquidity(s, 1, amount) },
+        Operation { name: "exchange 0 → 1", apply: |s, dx| exchange(s, 0, 1, dx) },
+        Operation { name: "exchange 1 → 0", apply: |s, dx| exchange(s, 1, 0, dx) },
+        Operation { name: "remove_liquidity", apply: remove_liquidity },
+        Operation { name: "remove_liquidity_one_coin 0", apply: |s, burn| remove_liquidity_one_coin(s, 0, burn) },
+        Operation { name: "remove_liquidity_one_coin 1", apply: |s, burn| remove_liquidity_one_coin(s, 1, burn) },
+    ],
+    variables: &[
+        Variable { name: "balance 0", get: |s| s.balances[0] },
+        Variable { name: "balance 1", get: |s| s.balances[1] },
+        Variable { name: "LP supply", get: |s| s.supply },
+        Variable { name: "D", get: D },
+        Variable { name: "virtual price", get: virtual_price },
+    ],
+    places: 18,
+};
+
+fn initial() -> PoolState {
+    let balance = u256d("1000000000000000000000");
+    let mut state = PoolState { balances: [balance, balance], supply: U256::zero() };
+    state.supply = D(&state);
+    state
+}
+
+fn D(state: &PoolState) -> U256 {
+    get_D(&state.balances, params::get(&AMP))
+}
+
+/// `D` per LP token, with 18 decimals
+fn virtual_price(state: &PoolState) -> U256 {
+    if state.supply.is_zero() { U256::zero() } else { div(mul(D(state), u256d("1000000000000000000")), state.supply) }
+}
+
+fn add_liquidity(state: &PoolState, i: usize, amount: U256) -> PoolState {
+    let D0 = if state.supply.is_zero() { U256::zero() } else { D(state) };
+    let mut new = state.clone();
+    new.balances[i] = add(new.balances[i], amount);
+    let D1 = D(&new);
+    assert!(D1 > D0, "D did not increase");
+    let minted = if state.supply.is_zero() { D1 } else { div(mul(state.supply, sub(D1, D0)), D0) };
+    new.supply = add(state.supply, minted);
+    new
+}
+
+fn exchange(state: &PoolState, i: usize, j: usize, dx: U256) -> PoolState {
+    let D0 = D(state);
+    let mut xp = state.balances;
+    xp[i] = add(xp[i], dx);
+    let y = get_y_D(params::get(&AMP), j, &xp, D0);
+    // One unit is kept back so rounding favours the pool
+    let dy = sub(sub(state.balances[j], y), u256f(1));
+    xp[j] = sub(state.balances[j], dy);
+    PoolState { balances: xp, supply: state.supply }
+}
+
+fn remove_liquidity(state: &PoolState, burn: U256) -> PoolState {
+    let mut new = state.clone();
+    for balance in &mut new.balances {
+        *balance = sub(*balance, div(mul(*balance, burn), state.supply));
+    }
+    new.supply = sub(state.supply, burn);
+    new
+}
+
+fn remove_liquidity_one_coin(state: &PoolState, i: usize, burn: U256) -> PoolState {
+    let D0 = D(state);
+    let D1 = sub(D0, div(mul(burn, D0), state.supply));
+    let new_y = get_y_D(params::get(&AMP), i, &state.balances, D1);
+    let dy = sub(sub(state.balances[i], new_y), u256f(1));
+    let mut new = state.clone();
+    new.balances[i] = sub(state.balances[i], dy);
+    new.supply = sub(state.supply, burn);
+    new
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::with_silent_panics;
+    use crate::scenario::{run, sweep, Step};
+
+    #[test]
+    fn test_curve_pool() -> Result<(), Box<dyn std::error::Error>> {
+        let start = initial();
+        let deposit = u256d("10000000000000000000");
+        let added = add_liquidity(&start, 0, deposit);
+        let minted = added.supply - start.supply;
+
+        // Withdrawing what a deposit minted mustn't return more than was deposited
+        let steps = [Step { operation: 0, amount: deposit }, Step { operation: 5, amount: minted }];
+        let result = with_silent_panics(|| run(&curve_pool, &steps));
+        assert!(result.revert.is_none());
+        assert!(result.states[1].balances[0] >= start.balances[0]);
+        assert_eq!(result.states[1].supply, start.supply);
+
+        // Burning more LP tokens than exist reverts at that step
+        let steps = [Step { operation: 4, amount: U256::zero() }];
+        let runs = sweep(&curve_pool, &steps, 0, &[u256d("1"), start.supply + 1]);
+        assert!(runs[0].revert.is_none());
+        assert_eq!(runs[1].revert.as_ref().map(|(i, _)| *i), Some(0));
+
+        // A huge exchange succeeds, but leaves a pool whose invariant can't be computed
+        let steps = [Step { operation: 2, amount: u256d("10000000000000000000000000000") }];
+        let runs = sweep(&curve_pool, &steps, 0, &[steps[0].amount]);
+        assert!(runs[0].revert.is_none());
+        let d = curve_pool.variables.iter().position(|v| v.name == "D").unwrap();
+        assert!(runs[0].values[0][d].is_err());
+        assert!(runs[0].values[0][0].is_ok());
+        Ok(())
+    }
+}
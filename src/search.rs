@@ -0,0 +1,227 @@
+use num_bigint::BigInt;
+use primitive_types::{U256};
+
+use crate::analysis::profit::RoundTrip;
+use crate::expr::Expr;
+use crate::maths::*;
+use crate::rational::{u256_to_bigint, Rational};
+use crate::reference::*;
+use crate::sampling::{eval, with_silent_panics};
+
+/*
+ * Global search over raw U256 inputs for the inputs that maximise an error objective.
+ *
+ * Sampling a few thousand evenly spaced points rarely lands on the worst rounding case, so the
+ * search alternates random restarts (uniform and log-uniform over the domain) with hill climbing,
+ * where each step tries a neighbouring integer, a jump of a random power of two or a bit flip.
+ */
+
+/* Non-improving neighbours tried before a hill climb gives up and restarts */
+const MAX_STALL: usize = 48;
+
+pub enum Objective {
+    /// Magnitude of the error against the function's reference, in the given unit
+    Reference(ErrorUnit),
+    /// Magnitude of `f(x) - g(x)` in raw units
+    Difference(&'static FixedPointFunction),
+    /// A user expression over `x` and `y` in raw units, plus `ref` (the floor of the reference)
+    /// when the function has one and `g` when a second function is given
+    Expression(Expr, Option<&'static FixedPointFunction>),
+    /// Profit of following the function with its inverse
+    RoundTrip(RoundTrip),
+}
+
+impl Objective {
+    /// The function's result and the objective at `x`, or `None` where the objective is undefined
+    /// (e.g. the function reverts)
+    pub fn score(&self, fun: &FixedPointFunction, x: U256) -> Option<(U256, Rational)> {
+        let y = eval(fun, x)?;
+        let score = match self {
+            Objective::Reference(unit) => {
+                let reference = (fun.reference?)(x);
+                exact_error(*unit, y, &reference, &fun.y_bounds).map(|err| err.abs())
+            }
+            Objective::Difference(other) => {
+                let other_y = eval(other, x)?;
+                Some(raw_error(y, &Rational::from_u256(other_y)).abs())
+            }
+            Objective::Expression(expr, other) => {
+                let reference = fun.reference.map(|r| r(x).floor());
+                let other_y = match other {
+                    Some(other) => Some(u256_to_bigint(eval(other, x)?)),
+                    None => None,
+                };
+                let env = |name: &str| -> Option<BigInt> {
+                    match name {
+                        "x" => Some(u256_to_bigint(x)),
+                        "y" => Some(u256_to_bigint(y)),
+                        "ref" => reference.clone(),
+                        "g" => other_y.clone(),
+                        _ => None,
+                    }
+                };
+                expr.eval(&env).ok().map(Rational::from_integer)
+            }
+            Objective::RoundTrip(round_trip) => round_trip.run(x, y).map(|(_, profit)| Rational::from_integer(profit)),
+        };
+        score.map(|score| (y, score))
+    }
+}
+
+pub struct SearchConfig {
+    /// Number of objective evaluations
+    pub budget: usize,
+    /// Number of worst inputs to report
+    pub top_k: usize,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub x: U256,
+    pub y: U256,
+    pub score: Rational,
+}
+
+/// SplitMix64, which is plenty for picking search moves
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, for `n > 0`
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    pub fn next_u256(&mut self) -> U256 {
+        U256([self.next_u64(), self.next_u64(), self.next_u64(), self.next_u64()])
+    }
+
+    /// A random point in `[lo, hi]`. Half of the points are log-uniform, so small offsets from
+    /// `lo` are explored as often as large ones
+    pub fn in_range(&mut self, lo: U256, hi: U256) -> U256 {
+        let span = hi - lo;
+        let offset = if self.below(2) == 0 {
+            self.next_u256()
+        } else {
+            let bits = self.below(span.bits() as u64 + 1) as usize;
+            if bits == 0 { U256::zero() } else { self.next_u256() >> (256 - bits) }
+        };
+        lo + if span == U256::MAX { offset } else { offset % (span + 1) }
+    }
+}
+
+/// A random move from `x`, clamped to `[lo, hi]`
+fn neighbour(rng: &mut Rng, x: U256, lo: U256, hi: U256) -> U256 {
+    let bits = hi.bits().max(1) as u64;
+    let up = rng.below(2) == 0;
+    let step = match rng.below(3) {
+        0 => U256::one(),
+        1 => U256::one() << rng.below(bits) as usize,
+        _ => return (x ^ (U256::one() << rng.below(bits) as usize)).clamp(lo, hi),
+    };
+    if up {
+        x.checked_add(step).unwrap_or(U256::MAX).min(hi)
+    } else {
+        x.saturating_sub(step).max(lo)
+    }
+}
+
+struct Searcher<'a> {
+    fun: &'a FixedPointFunction,
+    objective: &'a Objective,
+    top_k: usize,
+    top: Vec<Candidate>,
+    evals: usize,
+}
+
+impl Searcher<'_> {
+    fn evaluate(&mut self, x: U256) -> Option<Rational> {
+        self.evals += 1;
+        let (y, score) = self.objective.score(self.fun, x)?;
+        if !self.top.iter().any(|c| c.x == x) {
+            let pos = self.top.partition_point(|c| c.score >= score);
+            if pos < self.top_k {
+                self.top.insert(pos, Candidate { x, y, score: score.clone() });
+                self.top.truncate(self.top_k);
+            }
+        }
+        Some(score)
+    }
+}
+
+/// Searches `[lo, hi]` for the inputs with the largest objective. `starts` (e.g. the sampled inputs)
+/// are scored first and the best of them climbed from, then the search continues from random
+/// points until the budget is spent. Returns the best inputs found, worst first
+pub fn search(
+    fun: &FixedPointFunction,
+    objective: &Objective,
+    lo: U256,
+    hi: U256,
+    starts: &[U256],
+    config: &SearchConfig,
+) -> Vec<Candidate> {
+    let (lo, hi) = (lo.min(hi), lo.max(hi));
+    let mut rng = Rng(config.seed);
+    let mut searcher = Searcher { fun, objective, top_k: config.top_k, top: Vec::new(), evals: 0 };
+
+    with_silent_panics(|| {
+        let mut seeds: Vec<(Rational, U256)> = starts.iter()
+            .filter(|&&x| lo <= x && x <= hi)
+            .take(config.budget / 2)
+            .filter_map(|&x| searcher.evaluate(x).map(|score| (score, x)))
+            .collect();
+        seeds.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut seeds = seeds.into_iter().map(|(_, x)| x).take(config.top_k.max(1));
+
+        while searcher.evals < config.budget {
+            let mut x = seeds.next().unwrap_or_else(|| rng.in_range(lo, hi));
+            let Some(mut best) = searcher.evaluate(x) else { continue };
+
+            let mut stall = 0;
+            while stall < MAX_STALL && searcher.evals < config.budget {
+                let candidate = neighbour(&mut rng, x, lo, hi);
+                match searcher.evaluate(candidate) {
+                    Some(score) if score > best => {
+                        (x, best, stall) = (candidate, score, 0);
+                    }
+                    _ => stall += 1,
+                }
+            }
+        }
+    });
+    searcher.top
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parse;
+    use crate::functions::mul_by_inverse::x_mul_inverse;
+
+    #[test]
+    fn test_search() -> Result<(), Box<dyn std::error::Error>> {
+        let config = SearchConfig { budget: 3000, top_k: 3, seed: 1 };
+
+        // A monotonic objective is maximised at the top of the domain
+        let objective = Objective::Expression(parse("x")?, None);
+        let top = search(&x_mul_inverse, &objective, u256d("1"), u256d("1000000"), &[], &config);
+        assert_eq!(top[0].x, u256d("1000000"));
+        assert!(top.windows(2).all(|w| w[0].score >= w[1].score));
+
+        // Hill climbing from a sampled point must not report anything worse than it
+        let start = u256d("3000000000000000000");
+        let objective = Objective::Reference(ErrorUnit::Ulp);
+        let (_, start_score) = with_silent_panics(|| objective.score(&x_mul_inverse, start)).unwrap();
+        let top = search(&x_mul_inverse, &objective, u256d("1"), u256d("1000000000000000000000"), &[start], &config);
+        assert!(top[0].score >= start_score);
+        Ok(())
+    }
+}
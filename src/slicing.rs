@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use primitive_types::{U256};
+
+use crate::functions;
+use crate::maths::*;
+use crate::params::{self, Parameter};
+
+/*
+ * Functions of several inputs plotted as ordinary single-input functions: a slice along one input
+ * is a `FixedPointFunction` whose input is that one, while the others are read as parameters, so
+ * they are set the same way as any other parameter and every analysis works on the slice unchanged.
+ *
+ * A `FixedPointFunction` holds a plain `fn(U256) -> U256`, so each slice gets its own instance of
+ * `sliced`, picked from a table by the function's index in `functions::ALL_N` and the sliced input.
+ * Slices are built once per input and leaked, as the app holds functions by `&'static` reference.
+ */
+
+/* Most functions in `functions::ALL_N`, and most inputs any of them takes */
+const MAX_FUNCTIONS: usize = 4;
+const MAX_INPUTS: usize = 8;
+
+type Row = [fn(U256) -> U256; MAX_INPUTS];
+
+const SLICED: [Row; MAX_FUNCTIONS] = [row::<0>(), row::<1>(), row::<2>(), row::<3>()];
+
+const fn row<const F: usize>() -> Row {
+    [
+        sliced::<F, 0>, sliced::<F, 1>, sliced::<F, 2>, sliced::<F, 3>,
+        sliced::<F, 4>, sliced::<F, 5>, sliced::<F, 6>, sliced::<F, 7>,
+    ]
+}
+
+thread_local! {
+    static SLICES: RefCell<BTreeMap<(&'static str, usize), &'static FixedPointFunction>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// The slice of `fun`, one of `functions::ALL_N`, along its input `axis`
+pub fn slice(fun: &'static FixedPointFunctionN, axis: usize) -> &'static FixedPointFunction {
+    let index = functions::ALL_N.iter().position(|f| f.name == fun.name).expect("not in functions::ALL_N");
+    assert!(index < MAX_FUNCTIONS && fun.inputs.len() <= MAX_INPUTS, "raise MAX_FUNCTIONS or MAX_INPUTS");
+    SLICES.with(|s| *s.borrow_mut().entry((fun.name, axis)).or_insert_with(|| {
+        let input = &fun.inputs[axis];
+        let others: Vec<Parameter> = fun.inputs.iter()
+            .enumerate()
+            .filter(|&(i, _)| i != axis)
+            .map(|(_, input)| input.param)
+            .collect();
+        let b = &input.bounds;
+        Box::leak(Box::new(FixedPointFunction {
+            name: Box::leak(format!("{} along {}", fun.name, input.param.name).into_boxed_str()),
+            fun: SLICED[index][axis],
+            x_bounds: FixedPointBounds { radix: b.radix, places: b.places, min: b.min, max: b.max },
+            y_bounds: FixedPointBounds { ..fun.y_bounds },
+            num_points: FixedPointNumPoints { ..fun.num_points },
+            paths: fun.paths,
+            reference: None,
+            monotonicity: None,
+            rounding: None,
+            invariants: &[],
+            params: others.leak(),
+        }))
+    }))
+}
+
+/// Evaluates `functions::ALL_N[F]` with `x` as its input `AXIS`
+fn sliced<const F: usize, const AXIS: usize>(x: U256) -> U256 {
+    let fun = functions::ALL_N[F];
+    let inputs: Vec<U256> = fun.inputs.iter()
+        .enumerate()
+        .map(|(i, input)| if i == AXIS { x } else { params::get(&input.param) })
+        .collect();
+    (fun.fun)(&inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::curve::{curve_get_D_3, get_D};
+
+    #[test]
+    fn test_slice() -> Result<(), Box<dyn std::error::Error>> {
+        let one = u256d("1000000000000000000");
+        let along_b1 = slice(&curve_get_D_3, 1);
+        assert_eq!(along_b1.name, "curve_get_D_3 along b1");
+        assert_eq!(along_b1.params.iter().map(|p| p.name).collect::<Vec<_>>(), vec!["b0", "b2", "amp"]);
+        assert!(std::ptr::eq(along_b1, slice(&curve_get_D_3, 1)));
+
+        let x = u256d("5000000000000000000");
+        let amp = u256d("30000");
+        let along_b2 = slice(&curve_get_D_3, 2);
+        // Taking another slice doesn't change what an earlier one computes
+        assert_eq!((along_b1.fun)(x), get_D(&[one, x, one], amp));
+        let b0 = u256d("3000000000000000000");
+        let y = params::with_values(along_b2.params, &[b0, one, amp], || (along_b2.fun)(x));
+        assert_eq!(y, get_D(&[b0, one, x], amp));
+        Ok(())
+    }
+}
@@ -1,18 +1,54 @@
 use primitive_types::{U256};
+use crate::gas::{self, G_CHECK, G_LOW, G_VERYLOW};
+
+/*
+ * Vyper arithmetic. The plain operators are checked and revert on overflow, underflow and
+ * division by zero; the `unsafe_` variants wrap instead. Every operation charges its gas cost.
+ */
+
+pub fn add(a: U256, b: U256) -> U256 {
+    gas::charge(G_VERYLOW + G_CHECK);
+    return a.checked_add(b).expect("add overflow");
+}
+
+pub fn sub(a: U256, b: U256) -> U256 {
+    gas::charge(G_VERYLOW + G_CHECK);
+    return a.checked_sub(b).expect("sub underflow");
+}
+
+pub fn mul(a: U256, b: U256) -> U256 {
+    gas::charge(G_LOW + G_CHECK);
+    return a.checked_mul(b).expect("mul overflow");
+}
+
+pub fn div(a: U256, b: U256) -> U256 {
+    gas::charge(G_LOW + G_CHECK);
+    return a.checked_div(b).expect("division by zero");
+}
+
+pub fn pow(a: U256, b: U256) -> U256 {
+    gas::charge_exp(b);
+    gas::charge(G_CHECK);
+    return a.checked_pow(b).expect("pow overflow");
+}
 
 pub fn unsafe_div(a: U256, b: U256) -> U256 {
+    gas::charge(G_LOW);
     if b == U256::from(0) { return b } ;
     return a / b;
 }
 
 pub fn unsafe_sub(a: U256, b: U256) -> U256 {
+    gas::charge(G_VERYLOW);
     return a.overflowing_sub(b).0;
 }
 
 pub fn unsafe_add(a: U256, b: U256) -> U256 {
+    gas::charge(G_VERYLOW);
     return a.overflowing_add(b).0;
 }
 
 pub fn unsafe_mul(a: U256, b: U256) -> U256 {
+    gas::charge(G_LOW);
     return a.overflowing_mul(b).0;
 }